@@ -0,0 +1,222 @@
+use std::{
+    ffi::OsString,
+    fmt::{self, Write as _},
+    io::{self, Write as _},
+    process,
+};
+
+/// The hidden subcommand through which generated completions call back into
+/// the application, as `<bin> __complete <subcommand>... <arg id> <current>`.
+///
+/// For multi-word invocations, such as `cargo add`, the remaining words of
+/// the invocation come before `__complete`; see [`Completers::invocation`].
+pub const COMPLETE_COMMAND: &str = "__complete";
+
+/// A single completion candidate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    value: String,
+    help: Option<String>,
+}
+
+impl Candidate {
+    /// Create a new completion candidate.
+    pub fn new(value: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+            help: None,
+        }
+    }
+
+    /// Describe the candidate.
+    pub fn help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Get the completed value.
+    pub fn get_value(&self) -> &str {
+        &self.value
+    }
+
+    /// Get the description of the candidate, if any.
+    pub fn get_help(&self) -> Option<&str> {
+        self.help.as_deref()
+    }
+}
+
+impl From<String> for Candidate {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&str> for Candidate {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+type CompleterFn = Box<dyn Fn(&str) -> Vec<Candidate>>;
+
+struct Completer {
+    path: Vec<String>,
+    id: String,
+    complete: CompleterFn,
+}
+
+/// Completers for argument values that are only known at runtime.
+///
+/// Generated completions for registered arguments call back into the
+/// application through [`COMPLETE_COMMAND`], which must be handled before
+/// clap parses the command line:
+///
+/// ```rust
+/// use clap_completions::dynamic::Completers;
+///
+/// let completers = Completers::new()
+///     .register(&["checkout"], "branch", |current| {
+///         ["main", "develop"]
+///             .into_iter()
+///             .filter(|branch| branch.starts_with(current))
+///             .map(String::from)
+///             .collect::<Vec<_>>()
+///     });
+/// completers.complete_from_env();
+///
+/// // ... carry on parsing `std::env::args_os()` with clap as usual
+/// ```
+#[derive(Default)]
+pub struct Completers {
+    completers: Vec<Completer>,
+    prefix_len: usize,
+}
+
+impl fmt::Debug for Completers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries((self.completers.iter()).map(|completer| (&completer.path, &completer.id)))
+            .finish()
+    }
+}
+
+impl Completers {
+    /// Create an empty set of completers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expect the remaining words of a multi-word invocation, such as the
+    /// `add` of `["cargo", "add"]` for a cargo plugin, between the binary and
+    /// [`COMPLETE_COMMAND`], as generated for the same invocation.
+    pub fn invocation(mut self, invocation: &[&str]) -> Self {
+        self.prefix_len = invocation.len().saturating_sub(1);
+        self
+    }
+
+    /// Complete values of the argument `id` of the subcommand at `path`
+    /// (canonical subcommand names, excluding the binary) by calling
+    /// `complete` with the word currently being completed.
+    pub fn register<F, I>(mut self, path: &[&str], id: &str, complete: F) -> Self
+    where
+        F: Fn(&str) -> I + 'static,
+        I: IntoIterator,
+        I::Item: Into<Candidate>,
+    {
+        self.completers.push(Completer {
+            path: path.iter().map(|&name| name.into()).collect(),
+            id: id.into(),
+            complete: Box::new(move |current| {
+                complete(current).into_iter().map(Into::into).collect()
+            }),
+        });
+        self
+    }
+
+    /// Whether a completer is registered for argument `id` of the subcommand
+    /// at `path`.
+    pub fn contains(&self, path: &[&str], id: &str) -> bool {
+        self.find(path, id).is_some()
+    }
+
+    fn find(&self, path: &[&str], id: &str) -> Option<&Completer> {
+        (self.completers.iter())
+            .find(|completer| completer.id == id && completer.path.iter().eq(path))
+    }
+
+    /// Complete the command line `args` (including the binary name), if it
+    /// invokes [`COMPLETE_COMMAND`] for a registered argument.
+    pub fn complete<I, T>(&self, args: I) -> Option<Vec<Candidate>>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString>,
+    {
+        let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+        let args = (self.requested(&args)?.iter())
+            .map(|arg| arg.to_str())
+            .collect::<Option<Vec<_>>>()?;
+        match &*args {
+            [path @ .., id, current] => {
+                let completer = self.find(path, id)?;
+                Some((completer.complete)(current))
+            }
+            _ => None,
+        }
+    }
+
+    /// If this process was invoked as [`COMPLETE_COMMAND`], print the
+    /// completions as JSON to stdout and exit.
+    pub fn complete_from_env(&self) {
+        let args: Vec<_> = std::env::args_os().collect();
+        if self.requested(&args).is_none() {
+            return;
+        }
+
+        let candidates = self.complete(args).unwrap_or_default();
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        let result = writeln!(stdout, "{}", to_json(&candidates)).and_then(|()| stdout.flush());
+        process::exit(if result.is_ok() { 0 } else { 1 })
+    }
+
+    /// The words following [`COMPLETE_COMMAND`] in `args`, if it comes right
+    /// after the binary and the remaining words of the invocation.
+    fn requested<'s>(&self, args: &'s [OsString]) -> Option<&'s [OsString]> {
+        // Skip over the words of multi-word invocations, such as the `add`
+        // in `cargo add`, which are passed along to cargo plugins.
+        match args.get(1 + self.prefix_len..)? {
+            [command, rest @ ..] if command == COMPLETE_COMMAND => Some(rest),
+            _ => None,
+        }
+    }
+}
+
+/// Serialize `candidates` as a JSON list of `{ value, description }` records.
+fn to_json(candidates: &[Candidate]) -> String {
+    fn string(s: &str) -> String {
+        let mut json = String::with_capacity(s.len() + 2);
+        json.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => json.push_str("\\\""),
+                '\\' => json.push_str("\\\\"),
+                '\n' => json.push_str("\\n"),
+                '\r' => json.push_str("\\r"),
+                '\t' => json.push_str("\\t"),
+                c if c.is_control() => write!(json, "\\u{:04x}", c as u32).unwrap(),
+                c => json.push(c),
+            }
+        }
+        json.push('"');
+        json
+    }
+
+    let records: Vec<_> = (candidates.iter())
+        .map(|candidate| {
+            let value = string(candidate.get_value());
+            let description = string(candidate.get_help().unwrap_or(""));
+            format!("{{\"value\":{value},\"description\":{description}}}")
+        })
+        .collect();
+    format!("[{}]", records.join(","))
+}
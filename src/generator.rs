@@ -0,0 +1,102 @@
+use clap::Command;
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// A shell which completions can be generated for.
+///
+/// This is implemented by the shells supported by this crate, and for
+/// `clap_complete::Shell` with the `clap_complete` feature, so that all
+/// shells can be handled uniformly, for example from a build script:
+///
+/// ```rust,no_run
+/// # #[cfg(feature = "nu")] {
+/// use clap_completions::{generate_to, nu, Generator};
+///
+/// let app = clap::Command::new("my-app");
+/// let out_dir = std::env::var_os("OUT_DIR").unwrap();
+/// let shells: &[&dyn Generator] = &[&nu::Nu::new()];
+/// for shell in shells {
+///     generate_to(*shell, &app, &out_dir)?;
+/// }
+/// # }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub trait Generator {
+    /// The name of the shell.
+    fn name(&self) -> &str;
+
+    /// The file extension of completion files for this shell.
+    fn file_extension(&self) -> &str;
+
+    /// The name of the completion file for the binary `bin_name`.
+    fn file_name(&self, bin_name: &str) -> String {
+        format!("{bin_name}.{}", self.file_extension())
+    }
+
+    /// Write completions for `app` to `buf`.
+    fn generate(&self, app: &Command<'_>, buf: &mut dyn Write) -> io::Result<()>;
+}
+
+/// Write completions for `app` to a file in `out_dir`, named according to
+/// [`Generator::file_name`], returning the path of the file.
+pub fn generate_to(
+    generator: &dyn Generator,
+    app: &Command<'_>,
+    out_dir: impl AsRef<Path>,
+) -> io::Result<PathBuf> {
+    let app = prepare(app);
+    let bin_name = app.get_bin_name().unwrap_or_else(|| app.get_name());
+    let path = out_dir.as_ref().join(generator.file_name(bin_name));
+    let mut file = File::create(&path)?;
+    generator.generate(&app, &mut file)?;
+    file.flush()?;
+    Ok(path)
+}
+
+/// Prepare `app` for generating completions like `clap_complete::generate`
+/// does, so that every shell sees the same command: built, and with a bin
+/// name, defaulting to its display name or else its name.
+pub(crate) fn prepare<'help>(app: &Command<'help>) -> Command<'help> {
+    let mut app = app.clone();
+    if app.get_bin_name().is_none() {
+        let bin_name = (app.get_display_name())
+            .unwrap_or_else(|| app.get_name())
+            .to_string();
+        app.set_bin_name(bin_name);
+    }
+    app.build();
+    app
+}
+
+#[cfg(feature = "clap_complete")]
+impl Generator for clap_complete::Shell {
+    fn name(&self) -> &str {
+        clap::ValueEnum::to_possible_value(self)
+            .expect("no shells are skipped")
+            .get_name()
+    }
+
+    fn file_extension(&self) -> &str {
+        use clap_complete::Shell::*;
+        match self {
+            Bash => "bash",
+            Elvish => "elv",
+            Fish => "fish",
+            PowerShell => "ps1",
+            Zsh => "zsh",
+            _ => self.name(),
+        }
+    }
+
+    fn file_name(&self, bin_name: &str) -> String {
+        clap_complete::Generator::file_name(self, bin_name)
+    }
+
+    fn generate(&self, app: &Command<'_>, buf: &mut dyn Write) -> io::Result<()> {
+        clap_complete::Generator::generate(self, &prepare(app), buf);
+        Ok(())
+    }
+}
@@ -5,7 +5,12 @@
 //! - [nu][nushell]
 //!
 //! If you want support for other shells, please file an issue with links to
-//! resources explaining the structure of your shell's completion files.
+//! resources explaining the structure of your shell's completion files, or
+//! implement [`Generator`] for it in your own crate.
+//!
+//! With the `clap_complete` feature, [`Generator`] is implemented for
+//! `clap_complete::Shell` and the shells of this crate implement
+//! `clap_complete::Generator`, so they can be used interchangeably.
 //!
 //! See also:
 //!
@@ -41,6 +46,12 @@
 #[cfg(not(feature = "std"))]
 compile_error!("This crate requires the `std` feature to be enabled");
 
+mod generator;
+pub use generator::{generate_to, Generator};
+
+/// Runtime completion of argument values through the application itself.
+pub mod dynamic;
+
 /// Completions for [nushell].
 ///
 /// [nushell]: https://www.nushell.sh/
@@ -1,5 +1,243 @@
-use clap::{Arg, Command, ValueHint};
-use core::fmt::{self, Display};
+use crate::{
+    dynamic::{Completers, COMPLETE_COMMAND},
+    generator::prepare,
+    Generator,
+};
+use clap::{builder::ValueParser, Arg, ArgAction, Command, PossibleValue, ValueHint};
+use core::fmt::{self, Display, Write as _};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+};
+
+/// The [nushell] completions [`Generator`].
+///
+/// [nushell]: https://www.nushell.sh/
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Nu<'a> {
+    completers: Option<&'a Completers>,
+    examples: Option<&'a Examples>,
+    invocation: Option<&'a [&'a str]>,
+    version: NuVersion,
+    completion_algorithm: Option<CompletionAlgorithm>,
+    skipped_value_hints: &'a [ValueHint],
+    external_subcommands: ExternalSubcommands<'a>,
+}
+
+impl<'a> Nu<'a> {
+    /// Create a new completions generator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Complete the values of arguments registered with `completers` by
+    /// calling back into the application at completion time.
+    pub fn dynamic(mut self, completers: &'a Completers) -> Self {
+        self.completers = Some(completers);
+        self
+    }
+
+    /// Document commands with `examples`, in place of those extracted from
+    /// an `EXAMPLES:` section of their after help.
+    pub fn examples(mut self, examples: &'a Examples) -> Self {
+        self.examples = Some(examples);
+        self
+    }
+
+    /// Generate externs for the words users type to invoke the command, such
+    /// as `["cargo", "add"]` for a cargo plugin, rather than for its
+    /// [`Command::get_bin_name`], [`Command::get_display_name`] or
+    /// [`Command::get_name`].
+    pub fn invocation(mut self, invocation: &'a [&'a str]) -> Self {
+        self.invocation = Some(invocation);
+        self
+    }
+
+    /// Generate syntax understood by nushell `version`, rather than by the
+    /// latest release.
+    pub fn version(mut self, version: NuVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Match completions against the word being completed with `algorithm`,
+    /// rather than as configured by the user.
+    pub fn completion_algorithm(mut self, algorithm: CompletionAlgorithm) -> Self {
+        self.completion_algorithm = Some(algorithm);
+        self
+    }
+
+    /// Leave the values of args with any of `hints` to nushell's default
+    /// completion, rather than completing them with the built-in completer
+    /// for the hint, such as directories for [`ValueHint::DirPath`] or hosts
+    /// for [`ValueHint::Hostname`].
+    pub fn skip_value_hints(mut self, hints: &'a [ValueHint]) -> Self {
+        self.skipped_value_hints = hints;
+        self
+    }
+
+    /// Discover external subcommands of commands which
+    /// [allow them](Command::allow_external_subcommands) as configured.
+    pub fn external_subcommands(mut self, external_subcommands: ExternalSubcommands<'a>) -> Self {
+        self.external_subcommands = external_subcommands;
+        self
+    }
+
+    /// Generate completions for `app`.
+    pub fn completions<'help>(self, app: &'a Command<'help>) -> Completions<'a, 'help> {
+        Completions { app, nu: self }
+    }
+}
+
+/// A nushell release, selecting the syntax of generated completions.
+///
+/// Generated completions only use features available in the targeted
+/// version, and otherwise fall back to what older versions understand:
+///
+/// - Completers return their options along with their completions from
+///   0.98, keeping clap's order of values and its case sensitivity, and
+///   otherwise return a list of completions.
+/// - Command examples are written as `@example` attributes from 0.103, and
+///   otherwise left in the command's documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NuVersion {
+    major: u32,
+    minor: u32,
+}
+
+impl NuVersion {
+    /// The latest nushell release known to this crate.
+    pub const LATEST: Self = Self::new(0, 103);
+
+    /// The first release supporting the `sort` completion option, as listed
+    /// under custom completions in the Nushell 0.98.0 release notes.
+    const COMPLETION_OPTIONS: Self = Self::new(0, 98);
+
+    /// The first release supporting `@example` attributes, introduced with
+    /// attributes in the Nushell 0.103.0 release notes.
+    const ATTRIBUTES: Self = Self::new(0, 103);
+
+    /// The nushell release `major.minor`.
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+}
+
+impl Default for NuVersion {
+    fn default() -> Self {
+        Self::LATEST
+    }
+}
+
+impl Display for NuVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// How nushell matches completions against the word being completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompletionAlgorithm {
+    /// Completions starting with the word.
+    Prefix,
+    /// Completions containing the characters of the word, in order.
+    Fuzzy,
+}
+
+impl CompletionAlgorithm {
+    fn name(self) -> &'static str {
+        match self {
+            CompletionAlgorithm::Prefix => "prefix",
+            CompletionAlgorithm::Fuzzy => "fuzzy",
+        }
+    }
+}
+
+/// How external subcommands are discovered, for commands which
+/// [allow them](Command::allow_external_subcommands).
+///
+/// External subcommands are executables on `PATH` named with a prefix,
+/// like `cargo-clippy` for `cargo clippy`. Subcommands of the root command
+/// are discovered with the prefix `<bin>-` by default, and those of nested
+/// commands with the prefix extended by their subcommand path, such as
+/// `<bin>-remote-`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ExternalSubcommands<'a> {
+    prefix: Option<&'a str>,
+    description: ExternalSubcommandDescription<'a>,
+}
+
+impl<'a> ExternalSubcommands<'a> {
+    /// Discover external subcommands with the default prefix, without
+    /// descriptions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Discover external subcommands of the root command as executables
+    /// named `prefix` followed by the subcommand.
+    pub fn prefix(mut self, prefix: &'a str) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    /// Describe external subcommands from `description`.
+    pub fn description(mut self, description: ExternalSubcommandDescription<'a>) -> Self {
+        self.description = description;
+        self
+    }
+}
+
+/// Where descriptions of external subcommands come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalSubcommandDescription<'a> {
+    /// External subcommands are not described.
+    None,
+    /// The path of the executable.
+    Path,
+    /// The first line of the output of running the executable with the
+    /// given arguments, such as `&["--about"]`.
+    Output(&'a [&'a str]),
+}
+
+impl Default for ExternalSubcommandDescription<'_> {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl Generator for Nu<'_> {
+    fn name(&self) -> &str {
+        "nu"
+    }
+
+    fn file_extension(&self) -> &str {
+        "nu"
+    }
+
+    fn generate(&self, app: &Command<'_>, buf: &mut dyn io::Write) -> io::Result<()> {
+        let app = prepare(app);
+        write!(
+            buf,
+            "{}",
+            Completions {
+                app: &app,
+                nu: *self
+            }
+        )
+    }
+}
+
+#[cfg(feature = "clap_complete")]
+impl clap_complete::Generator for Nu<'_> {
+    fn file_name(&self, name: &str) -> String {
+        Generator::file_name(self, name)
+    }
+
+    fn generate(&self, app: &Command<'_>, buf: &mut dyn io::Write) {
+        Generator::generate(self, app, buf).expect("failed to write completion file")
+    }
+}
 
 /// Completions for [nushell].
 ///
@@ -7,36 +245,293 @@ use core::fmt::{self, Display};
 #[derive(Debug, Clone, Copy)]
 pub struct Completions<'a, 'help> {
     app: &'a Command<'help>,
+    nu: Nu<'a>,
 }
 
 impl<'a, 'help> Completions<'a, 'help> {
     /// Create a new completions generator.
+    ///
+    /// `app` is used as is; call [`Command::build`] first to include the
+    /// `help` subcommands and flags clap generates, as [`Generator::generate`]
+    /// does.
     pub fn new(app: &'a Command<'help>) -> Self {
-        Self { app }
+        Nu::new().completions(app)
+    }
+
+    /// Complete the values of arguments registered with `completers` by
+    /// calling back into the application at completion time.
+    pub fn dynamic(mut self, completers: &'a Completers) -> Self {
+        self.nu = self.nu.dynamic(completers);
+        self
+    }
+
+    /// Document commands with `examples`.
+    ///
+    /// See [`Nu::examples`].
+    pub fn examples(mut self, examples: &'a Examples) -> Self {
+        self.nu = self.nu.examples(examples);
+        self
+    }
+
+    /// Generate externs for the words users type to invoke the command.
+    ///
+    /// See [`Nu::invocation`].
+    pub fn invocation(mut self, invocation: &'a [&'a str]) -> Self {
+        self.nu = self.nu.invocation(invocation);
+        self
+    }
+
+    /// Generate syntax understood by nushell `version`.
+    ///
+    /// See [`Nu::version`].
+    pub fn version(mut self, version: NuVersion) -> Self {
+        self.nu = self.nu.version(version);
+        self
+    }
+
+    /// Match completions with `algorithm`.
+    ///
+    /// See [`Nu::completion_algorithm`].
+    pub fn completion_algorithm(mut self, algorithm: CompletionAlgorithm) -> Self {
+        self.nu = self.nu.completion_algorithm(algorithm);
+        self
+    }
+
+    /// Leave the values of args with any of `hints` to nushell.
+    ///
+    /// See [`Nu::skip_value_hints`].
+    pub fn skip_value_hints(mut self, hints: &'a [ValueHint]) -> Self {
+        self.nu = self.nu.skip_value_hints(hints);
+        self
+    }
+
+    /// Discover external subcommands as configured.
+    ///
+    /// See [`Nu::external_subcommands`].
+    pub fn external_subcommands(mut self, external_subcommands: ExternalSubcommands<'a>) -> Self {
+        self.nu = self.nu.external_subcommands(external_subcommands);
+        self
+    }
+
+    /// Whether values with `hint` are completed by its built-in completer.
+    fn completes_value_hint(&self, hint: ValueHint) -> bool {
+        !self.nu.skipped_value_hints.contains(&hint)
+    }
+
+    /// The words users type to invoke the root command.
+    fn invocation_words(&self) -> Vec<&'a str> {
+        match self.nu.invocation {
+            Some(invocation) => invocation.to_vec(),
+            None => (self.app.get_bin_name())
+                .or_else(|| self.app.get_display_name())
+                .unwrap_or_else(|| self.app.get_name())
+                .split_whitespace()
+                .collect(),
+        }
     }
 }
 
 impl Display for Completions<'_, '_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write_completion_module(self.app, f)
+        write_completion_module(self, f)
     }
 }
 
-fn write_completion_module(app: &Command<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    let name = app.get_name();
+fn write_completion_module(
+    completions: &Completions<'_, '_>,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    let app = completions.app;
+    let name = completions.invocation_words().join(" ");
 
-    write_nu_completes(app, f, FullCommandName { name, parent: None })?;
-    write_export_externs(app, f, FullCommandName { name, parent: None })?;
+    let name = FullCommandName {
+        name: &name,
+        parent: None,
+    };
+    let mut helpers = Helpers::new(completions, app, name)?;
+    write_nu_completes(completions, &mut helpers, app, f, &[], name)?;
+    write_export_externs(completions, &helpers, app, f, &[], name, name)?;
 
     Ok(())
 }
 
+/// A usage example of a command, shown by nushell's `help`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Example {
+    description: String,
+    invocation: String,
+    result: Option<String>,
+}
+
+impl Example {
+    /// Create a new example of running `invocation`, such as
+    /// `"my-app build --release"`.
+    pub fn new(description: impl Into<String>, invocation: impl Into<String>) -> Self {
+        Self {
+            description: description.into(),
+            invocation: invocation.into(),
+            result: None,
+        }
+    }
+
+    /// The output of the example.
+    pub fn result(mut self, result: impl Into<String>) -> Self {
+        self.result = Some(result.into());
+        self
+    }
+
+    /// Get the description of the example.
+    pub fn get_description(&self) -> &str {
+        &self.description
+    }
+
+    /// Get the example command line.
+    pub fn get_invocation(&self) -> &str {
+        &self.invocation
+    }
+
+    /// Get the output of the example, if any.
+    pub fn get_result(&self) -> Option<&str> {
+        self.result.as_deref()
+    }
+}
+
+/// Usage examples of a command tree.
+///
+/// ```rust
+/// use clap_completions::nu::{Example, Examples, Nu};
+///
+/// let examples = Examples::new()
+///     .add(&[], Example::new("Show the version", "my-app --version").result("my-app 1.0"))
+///     .add(&["build"], Example::new("Build in release mode", "my-app build --release"));
+///
+/// let app = clap::Command::new("my-app").subcommand(clap::Command::new("build"));
+/// let completions = Nu::new().examples(&examples).completions(&app).to_string();
+/// assert!(completions.contains("@example"));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Examples {
+    examples: Vec<(Vec<String>, Example)>,
+}
+
+impl Examples {
+    /// Create an empty set of examples.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `example` to the subcommand at `path` (canonical subcommand
+    /// names, excluding the binary).
+    pub fn add(mut self, path: &[&str], example: Example) -> Self {
+        let path = path.iter().map(|&name| name.into()).collect();
+        self.examples.push((path, example));
+        self
+    }
+
+    /// The examples of the subcommand at `path`.
+    pub fn get<'a>(&'a self, path: &'a [&str]) -> impl Iterator<Item = &'a Example> + 'a {
+        (self.examples.iter())
+            .filter(move |(example_path, _)| example_path.iter().eq(path))
+            .map(|(_, example)| example)
+    }
+}
+
+/// Best-effort extraction of examples from an `EXAMPLES:` section of `help`,
+/// returning `help` without the section and the examples found in it.
+///
+/// Within the section, lines starting with `$ ` or `bin` are taken to be
+/// invocations, described by the text (or `#` comments) preceding them.
+fn extract_examples(help: &str, bin: &str) -> (String, Vec<Example>) {
+    let lines: Vec<_> = help.lines().collect();
+    let is_heading = |line: &&str| line.trim_end().eq_ignore_ascii_case("examples:");
+    let start = match lines.iter().position(is_heading) {
+        Some(start) => start,
+        None => return (help.to_string(), vec![]),
+    };
+    // The section extends over the following blank or indented lines.
+    let end = (lines[start + 1..].iter())
+        .position(|line| !line.trim().is_empty() && !line.starts_with(char::is_whitespace))
+        .map_or(lines.len(), |len| start + 1 + len);
+
+    let mut examples = vec![];
+    let mut description: Vec<&str> = vec![];
+    for line in &lines[start + 1..end] {
+        let line = line.trim();
+        let invocation = (line.strip_prefix("$ "))
+            .or_else(|| (line.split_whitespace().next() == Some(bin)).then(|| line));
+        match invocation {
+            Some(invocation) => {
+                let description = description.join(" ");
+                let description = description.trim_end_matches(':');
+                examples.push(Example::new(description, invocation.trim()));
+            }
+            None if line.is_empty() => continue,
+            None => description.push(line.trim_start_matches('#').trim()),
+        }
+        if invocation.is_some() {
+            description.clear();
+        }
+    }
+
+    if examples.is_empty() {
+        return (help.to_string(), examples);
+    }
+    let rest = [&lines[..start], &lines[end..]].concat().join("\n");
+    (rest.trim().to_string(), examples)
+}
+
+/// An `EXAMPLES:` section documenting `examples`, for releases without
+/// `@example` attributes.
+fn examples_section(examples: &[Example]) -> String {
+    let mut section = String::from("EXAMPLES:");
+    for (i, example) in examples.iter().enumerate() {
+        if i > 0 {
+            section.push('\n');
+        }
+        for line in example.get_description().lines() {
+            section.push_str(&format!("\n    # {line}"));
+        }
+        section.push_str(&format!("\n    $ {}", example.get_invocation()));
+        for line in example.get_result().unwrap_or("").lines() {
+            section.push_str(&format!("\n    {line}"));
+        }
+    }
+    section
+}
+
 #[derive(Debug, Clone, Copy)]
 struct FullCommandName<'a> {
     name: &'a str,
     parent: Option<&'a FullCommandName<'a>>,
 }
 
+impl<'a> FullCommandName<'a> {
+    /// The words invoking this command: those invoking the root command,
+    /// followed by the subcommand names leading to this one.
+    fn words(&self) -> Vec<&'a str> {
+        match self.parent {
+            Some(parent) => {
+                let mut words = parent.words();
+                words.push(self.name);
+                words
+            }
+            None => self.name.split(' ').collect(),
+        }
+    }
+
+    /// The subcommand names leading from the root command to this one.
+    fn path(&self) -> Vec<&'a str> {
+        match self.parent {
+            Some(parent) => {
+                let mut path = parent.path();
+                path.push(self.name);
+                path
+            }
+            None => vec![],
+        }
+    }
+}
+
 impl Display for FullCommandName<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let FullCommandName { name, parent } = self;
@@ -47,152 +542,1308 @@ impl Display for FullCommandName<'_> {
     }
 }
 
+/// Quote `s` as a nushell double-quoted string literal.
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Quote `s` as a nushell string literal, as a raw single-quoted string if
+/// possible so that ordinary command names are written as users expect.
+fn quote_name(s: &str) -> String {
+    if s.is_empty() || s.contains(|c: char| c == '\'' || c.is_control()) {
+        quote(s)
+    } else {
+        format!("'{s}'")
+    }
+}
+
+/// The first line of `s`, for use in a `#` comment.
+fn comment(s: &str) -> &str {
+    s.split(['\r', '\n']).next().unwrap()
+}
+
+/// The lines of `s` after the first, as `#` comment lines continuing the
+/// description of the preceding parameter.
+fn param_doc_comment(s: &str) -> String {
+    let mut doc = String::new();
+    for line in s.lines().skip(1) {
+        // nushell trims parameter comments anyway.
+        match line.trim() {
+            "" => doc.push_str("\n  #"),
+            line => doc.push_str(&format!("\n  # {line}")),
+        }
+    }
+    doc
+}
+
+/// Write `s` as `#` comment lines, which nushell shows as the description
+/// (the first line) and extended description (the rest) of a command.
+fn write_doc_comment(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    for line in s.lines() {
+        match line.trim_end() {
+            "" => writeln!(f, "#")?,
+            line => writeln!(f, "# {line}")?,
+        }
+    }
+    Ok(())
+}
+
+/// Sanitize `s` into a nushell parameter name.
+fn nu_identifier(s: &str) -> String {
+    let ident: String = (s.chars())
+        .map(|c| match c {
+            c if c.is_alphanumeric() || c == '_' || c == '-' => c,
+            _ => '_',
+        })
+        .collect();
+    match ident.chars().next() {
+        Some(c) if c.is_alphabetic() || c == '_' => ident,
+        _ => format!("_{ident}"),
+    }
+}
+
+/// Whether `s` can be spelled as a flag name in a nushell signature.
+///
+/// Flag names cannot be quoted, so flags with other names are left out.
+fn is_nu_flag(s: &str) -> bool {
+    !s.is_empty() && (s.chars()).all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// The name of the `nu-complete` helper completing `arg`'s values.
+///
+/// Helpers are named `nu-complete <command words>... --<id>`, after the
+/// words invoking the command which `arg` belongs to. Users may refer to
+/// helpers from their own configuration, so this naming is kept stable.
+/// Args completed by the same static values as another arg are the
+/// exception: they share a `nu-complete-values-<hash>` helper instead, which
+/// keeps the module small at the cost of stable names for those args.
+///
+/// Each word is escaped by [`helper_word`], so that distinct commands and
+/// args always have distinct helpers: words are separated by unescaped
+/// spaces, and the last word is the id.
+fn nu_complete_name(name: FullCommandName<'_>, arg: &Arg<'_>) -> String {
+    let mut helper = String::from("nu-complete");
+    for word in name.words() {
+        helper.push(' ');
+        helper.push_str(&helper_word(word));
+    }
+    helper.push_str(" --");
+    helper.push_str(&helper_word(arg.get_id()));
+    helper
+}
+
+/// The name of the `nu-complete` helper completing the built-in and
+/// external subcommands of the command `name`.
+fn nu_complete_subcommands_name(name: FullCommandName<'_>) -> String {
+    let mut helper = String::from("nu-complete");
+    for word in name.words() {
+        helper.push(' ');
+        helper.push_str(&helper_word(word));
+    }
+    helper.push_str(" (subcommands)");
+    helper
+}
+
+/// Escape `word` for use in a helper name, by percent-encoding `%`,
+/// whitespace, `'` and control characters.
+///
+/// nushell looks up completers by their literal, unescaped, name, so names
+/// must be writable in single quotes.
+fn helper_word(word: &str) -> String {
+    let mut escaped = String::with_capacity(word.len());
+    for c in word.chars() {
+        if c == '%' || c == '\'' || c.is_whitespace() || c.is_control() {
+            for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                escaped.push_str(&format!("%{byte:02X}"));
+            }
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
 fn nu_value_hint(hint: ValueHint) -> &'static str {
     use ValueHint::*;
     #[allow(clippy::wildcard_in_or_patterns)]
     #[cfg_attr(nightly, warn(non_exhaustive_omitted_patterns))]
     match hint {
         Unknown => "string",
-        AnyPath | FilePath | DirPath | ExecutablePath => "path",
+        DirPath => "directory",
+        AnyPath | FilePath | ExecutablePath => "path",
         CommandName | CommandString | CommandWithArguments | Username | Hostname | Url
         | EmailAddress => "string",
         Other | _ => "any",
     }
 }
 
-fn write_nu_completes(
-    app: &Command<'_>,
-    f: &mut fmt::Formatter<'_>,
+/// A nu expression listing executables on `PATH`: files with an execute
+/// permission, or on Windows, files with an extension from `PATHEXT`, which
+/// are listed without it as they are invoked.
+const EXECUTABLES: &str = "$env.PATH? | default ($env.Path? | default []) \
+    | where ($it | path exists) \
+    | each {|dir| ls -l $dir | where type != dir } \
+    | flatten \
+    | if $nu.os-info.name == 'windows' { \
+        where ('.' + ($it.name | path parse | get extension | str downcase)) \
+            in ($env.PATHEXT? | default '.COM;.EXE;.BAT;.CMD' | str downcase | split row ';') \
+        | each {|entry| $entry.name | path parse | get stem } \
+    } else { \
+        where mode =~ 'x' | each {|entry| $entry.name | path basename } \
+    } \
+    | uniq";
+
+/// A nu expression listing local users from `/etc/passwd`.
+const USERS: &str = "['/etc/passwd'] | where ($it | path exists) \
+    | each {|file| open --raw $file | lines | where $it !~ '^\\s*(#|$)' \
+        | each {|line| $line | split row ':' | first } } \
+    | flatten";
+
+/// A nu expression listing hosts from `/etc/hosts` and `~/.ssh/config`.
+const HOSTS: &str = "['/etc/hosts'] | where ($it | path exists) \
+    | each {|file| open --raw $file | lines \
+        | each {|line| $line | split row '#' | first | str trim | split row -r '\\s+' | skip 1 } \
+        | flatten } \
+    | append ([($nu.home-path | path join .ssh config)] | where ($it | path exists) \
+        | each {|file| open --raw $file | lines \
+            | each {|line| $line | str trim | split row -r '\\s+' } \
+            | where ($it | first | str downcase) == 'host' \
+            | each {|words| $words | skip 1 } } \
+        | flatten) \
+    | flatten | where $it != '' and $it !~ '[*?!]' | uniq";
+
+/// A nu expression for the value being completed, given the command line up
+/// to the cursor as `$context`, without the flag of a `--flag=value` word.
+const CURRENT_VALUE: &str = "$context | split row ' ' | last | str replace -r '^--?[^=-][^=]*=' ''";
+
+/// A nu statement completing nothing unless the word being completed is a
+/// `--flag=value` word, for args whose values are only written that way.
+const REQUIRE_EQUALS: &str =
+    "if ($context | split row ' ' | last) !~ '^--[^=-][^=]*=' { return [] }";
+
+/// A nu expression listing the values of args with `hint`, for hints which
+/// nushell does not complete by type.
+fn nu_value_hint_completions(hint: ValueHint) -> Option<&'static str> {
+    use ValueHint::*;
+    match hint {
+        ExecutablePath | CommandName => Some(EXECUTABLES),
+        Username => Some(USERS),
+        Hostname => Some(HOSTS),
+        _ => None,
+    }
+}
+
+/// How the values of an argument are completed.
+enum ValueCompleter<'help> {
+    /// Offering a fixed list of possible values.
+    Static(Vec<PossibleValue<'help>>),
+    /// With the built-in completer for the arg's [`ValueHint`], evaluating
+    /// the given nu expression.
+    Hint(&'static str),
+    /// Completing the words of a [`ValueHint::CommandWithArguments`]
+    /// positional as a command line of their own.
+    Command,
+    /// Completing the path of a subcommand to show the help of, with the
+    /// helper written along with the parent of the `help` subcommand.
+    HelpPath,
+    /// Calling back into the application through [`COMPLETE_COMMAND`].
+    Dynamic,
+}
+
+/// How to complete the values of `arg` of the command `name`, if at all.
+///
+/// This decides both whether a `nu-complete` helper is defined for `arg`
+/// and whether its parameter refers to that helper.
+fn value_completer<'help>(
+    completions: &Completions<'_, '_>,
     name: FullCommandName<'_>,
+    arg: &Arg<'help>,
+) -> Option<ValueCompleter<'help>> {
+    if !arg.is_takes_value_set() && !arg.is_positional() {
+        return None;
+    }
+
+    let completers = completions.nu.completers;
+    if completers.map_or(false, |completers| {
+        completers.contains(&name.path(), arg.get_id())
+    }) {
+        return Some(ValueCompleter::Dynamic);
+    }
+
+    if let Some(possible_values) = possible_values(arg) {
+        return Some(ValueCompleter::Static(possible_values));
+    }
+
+    if is_help_path(name.name, arg) {
+        return Some(ValueCompleter::HelpPath);
+    }
+
+    let hint = arg.get_value_hint();
+    if hint == ValueHint::CommandWithArguments && arg.is_positional() {
+        return (completions.completes_value_hint(hint)).then(|| ValueCompleter::Command);
+    }
+    (nu_value_hint_completions(hint))
+        .filter(|_| completions.completes_value_hint(hint))
+        .map(ValueCompleter::Hint)
+}
+
+/// Whether `arg` of the command `name` is the subcommand path taken by the
+/// `help` subcommand clap generates.
+fn is_help_path(name: &str, arg: &Arg<'_>) -> bool {
+    name == "help" && arg.get_id() == "subcommand" && arg.is_positional()
+}
+
+/// The values to complete for `arg`, if clap knows of any.
+fn possible_values<'help>(arg: &Arg<'help>) -> Option<Vec<PossibleValue<'help>>> {
+    #[allow(deprecated)]
+    match arg.get_possible_values() {
+        Some(possible_values) => Some(possible_values.to_vec()),
+        None => (arg.get_value_parser().possible_values())
+            .map(|possible_values| possible_values.collect()),
+    }
+}
+
+/// Arguments accepted by `app`, including `globals` inherited from its
+/// ancestors which have not already been propagated by [`Command::build`].
+fn all_arguments<'a, 'help>(
+    app: &'a Command<'help>,
+    globals: &[&'a Arg<'help>],
+) -> Vec<&'a Arg<'help>> {
+    let mut args: Vec<_> = app.get_arguments().collect();
+    // Every command has its own generated `help` and `version` flags, which
+    // `Command::build` removes again wherever they don't apply.
+    let globals =
+        (globals.iter().copied()).filter(|arg| !matches!(arg.get_id(), "help" | "version"));
+    for global in globals {
+        if !args.iter().any(|arg| arg.get_id() == global.get_id()) {
+            args.push(global);
+        }
+    }
+    args
+}
+
+/// The `<VALUE>` labels of `arg`'s values, as shown by clap's help.
+fn value_label(arg: &Arg<'_>) -> Option<String> {
+    let value_names = arg.get_value_names()?;
+    let labels: Vec<_> = value_names.iter().map(|name| format!("<{name}>")).collect();
+    Some(labels.join(" "))
+}
+
+/// The name of the nu parameter for positional `arg`: its value name where
+/// it has a single one, else its id.
+fn nu_param_name(arg: &Arg<'_>) -> String {
+    match arg.get_value_names() {
+        Some([value_name]) => nu_identifier(value_name),
+        _ => nu_identifier(arg.get_id()),
+    }
+}
+
+/// A positional argument as declared in a nu signature.
+struct Positional<'a, 'help> {
+    arg: &'a Arg<'help>,
+    name: String,
+    required: bool,
+    /// Whether this is the rest parameter, collecting all remaining words.
+    rest: bool,
+    /// Positionals after the rest parameter, which nu cannot declare.
+    folded: Vec<&'a Arg<'help>>,
+}
+
+/// The visible positionals of `app` from `args`, in the shape nu accepts:
+/// ordered by index, no required positional after an optional one, and at
+/// most one rest parameter, which comes last.
+fn nu_positionals<'a, 'help>(
+    app: &Command<'help>,
+    args: &[&'a Arg<'help>],
+) -> Vec<Positional<'a, 'help>> {
+    // Parameters share one namespace of variables with flags, where `-`
+    // and `_` are interchangeable.
+    let variable = |name: &str| name.replace('-', "_");
+    let flags: Vec<_> = (args.iter())
+        .filter(|arg| !arg.is_positional())
+        .map(|arg| variable(&nu_identifier(arg.get_long().unwrap_or(arg.get_id()))))
+        .collect();
+
+    let mut args: Vec<_> = (args.iter().copied())
+        .filter(|arg| arg.is_positional() && !arg.is_hide_set())
+        .collect();
+    args.sort_by_key(|arg| arg.get_index().unwrap_or(usize::MAX));
+
+    let mut positionals: Vec<Positional<'_, '_>> = Vec::with_capacity(args.len());
+    let mut optional = false;
+    for (i, arg) in args.iter().copied().enumerate() {
+        if let Some(rest) = positionals.last_mut().filter(|last| last.rest) {
+            rest.folded.push(arg);
+            continue;
+        }
+
+        let is_final = i == args.len() - 1;
+        let takes_many = match arg.get_num_vals() {
+            Some(num_vals) => num_vals > 1,
+            None => arg.is_multiple_values_set(),
+        };
+        let repeatable =
+            arg.is_multiple_occurrences_set() || matches!(arg.get_action(), ArgAction::Append);
+        optional |= !arg.is_required_set();
+        positionals.push(Positional {
+            arg,
+            name: nu_param_name(arg),
+            required: !optional,
+            rest: takes_many
+                || repeatable
+                || arg.is_last_set()
+                || (is_final && app.is_trailing_var_arg_set()),
+            folded: Vec::new(),
+        });
+    }
+
+    // Value names need not be unique, unlike ids.
+    let names: Vec<_> = (positionals.iter())
+        .map(|positional| variable(&positional.name))
+        .collect();
+    for positional in &mut positionals {
+        let name = variable(&positional.name);
+        if flags.contains(&name) || names.iter().filter(|&other| *other == name).count() > 1 {
+            positional.name = nu_identifier(positional.arg.get_id());
+        }
+    }
+    positionals
+}
+
+/// The nushell type of the values of `value_parser`, if known.
+fn nu_value_parser(value_parser: &ValueParser) -> Option<&'static str> {
+    let type_id = value_parser.type_id();
+    // Parsers such as `BoolishValueParser` produce a `bool` from more inputs
+    // than nushell's `true` and `false`.
+    if type_id == (&bool::default()).into() {
+        let possible_values: Option<Vec<_>> = (value_parser.possible_values())
+            .map(|possible_values| possible_values.map(|pv| pv.get_name()).collect());
+        return match possible_values.as_deref() {
+            Some(["true", "false"]) => Some("bool"),
+            _ => None,
+        };
+    }
+
+    macro_rules! match_type_id {
+        ($($ty:ty => $nu_type:literal,)*) => {
+            $(if type_id == (&<$ty>::default()).into() {
+                return Some($nu_type);
+            })*
+        };
+    }
+
+    // nushell's `int` is an `i64`, so it holds every value of the narrower
+    // unsigned types, though it also accepts the negative values clap
+    // rejects for them. `u64` and `usize` values may not fit, and are left
+    // as strings.
+    match_type_id! {
+        u8 => "int",
+        i8 => "int",
+        u16 => "int",
+        i16 => "int",
+        u32 => "int",
+        i32 => "int",
+        i64 => "int",
+        isize => "int",
+        f32 => "number",
+        f64 => "number",
+        std::path::PathBuf => "path",
+    }
+
+    None
+}
+
+/// The nushell type of `arg`'s values, preferring the parsed type over the
+/// value hint so that nushell rejects the same malformed values clap would.
+fn nu_type(completions: &Completions<'_, '_>, arg: &Arg<'_>) -> &'static str {
+    let hint = arg.get_value_hint();
+    match nu_value_parser(arg.get_value_parser()) {
+        Some(nu_type) => nu_type,
+        None if !completions.completes_value_hint(hint) && hint == ValueHint::DirPath => "path",
+        None => nu_value_hint(hint),
+    }
+}
+
+/// The helpers completing args' values.
+///
+/// Args with identical static completions, such as the same possible values
+/// repeated across subcommands, are completed by a single helper named after
+/// its body, which their externs refer to in place of their own helpers.
+#[derive(Debug, Default)]
+struct Helpers {
+    /// The shared helper completing each arg, by the arg's own helper name.
+    shared: HashMap<String, String>,
+    /// The helpers written so far.
+    written: HashSet<String>,
+}
+
+impl Helpers {
+    /// Find the args of `app` and its subcommands with the same static
+    /// completions.
+    fn new<'a, 'help>(
+        completions: &Completions<'_, '_>,
+        app: &'a Command<'help>,
+        name: FullCommandName<'_>,
+    ) -> Result<Self, fmt::Error> {
+        let mut bodies = Vec::new();
+        collect_static_completers(completions, app, &[], name, &mut bodies)?;
+
+        let mut uses = HashMap::<&str, usize>::new();
+        for (_, _, body) in &bodies {
+            *uses.entry(body).or_default() += 1;
+        }
+        let shared = (bodies.iter())
+            .filter(|(_, _, body)| uses[body.as_str()] > 1)
+            .map(|(helper, _, body)| (helper.clone(), shared_helper_name(body)))
+            .collect();
+
+        Ok(Self {
+            shared,
+            written: HashSet::new(),
+        })
+    }
+
+    /// The name of the helper completing `arg`'s values.
+    fn name(&self, name: FullCommandName<'_>, arg: &Arg<'_>) -> String {
+        let helper = nu_complete_name(name, arg);
+        match self.shared.get(&helper) {
+            Some(shared) => shared.clone(),
+            None => helper,
+        }
+    }
+}
+
+/// Collect the helper name, parameters and body of every arg of `app` and
+/// its subcommands with static completions.
+fn collect_static_completers<'a, 'help>(
+    completions: &Completions<'_, '_>,
+    app: &'a Command<'help>,
+    globals: &[&'a Arg<'help>],
+    name: FullCommandName<'_>,
+    bodies: &mut Vec<(String, &'static str, String)>,
 ) -> fmt::Result {
     if app.is_hide_set() {
         return Ok(());
     }
 
-    for arg in app.get_arguments() {
+    let args = all_arguments(app, globals);
+    for &arg in &args {
         if arg.is_hide_set() {
             continue;
         }
+        if let Some((params, body)) = static_completer(completions, name, arg)? {
+            bodies.push((nu_complete_name(name, arg), params, body));
+        }
+    }
+
+    let globals: Vec<_> = args.into_iter().filter(|arg| arg.is_global_set()).collect();
+    for app in app.get_subcommands() {
+        let parent = Some(&name);
+        let name = app.get_name();
+        collect_static_completers(
+            completions,
+            app,
+            &globals,
+            FullCommandName { name, parent },
+            bodies,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// The name of the helper shared between args completed by `body`.
+///
+/// The name is derived from the body alone, with FNV-1a so that it is
+/// stable across builds, and is a single word nushell can call unquoted.
+fn shared_helper_name(body: &str) -> String {
+    let hash = (body.bytes()).fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("nu-complete-values-{hash:016x}")
+}
+
+/// The parameters and body of the helper completing `arg`, if its
+/// completions are static, depending only on the command line.
+fn static_completer(
+    completions: &Completions<'_, '_>,
+    name: FullCommandName<'_>,
+    arg: &Arg<'_>,
+) -> Result<Option<(&'static str, String)>, fmt::Error> {
+    let case_sensitive = !arg.is_ignore_case_set();
+    let mut params = "";
+    let mut body = String::new();
+    match value_completer(completions, name, arg) {
+        Some(ValueCompleter::Static(possible_values)) => {
+            if require_equals(arg) {
+                params = "context: string";
+                writeln!(body, "  {REQUIRE_EQUALS}")?;
+            }
+            match value_delimiter(arg) {
+                Some(delimiter) => {
+                    params = "context: string";
+                    write_delimited_values(
+                        completions,
+                        case_sensitive,
+                        &mut body,
+                        delimiter,
+                        possible_values,
+                    )?;
+                }
+                None => {
+                    write_completer_body(completions, case_sensitive, &mut body, |f, indent| {
+                        write_possible_values(f, indent, false, possible_values)
+                    })?;
+                }
+            }
+        }
+        Some(ValueCompleter::Hint(values)) => {
+            write_completer_body(completions, case_sensitive, &mut body, |f, _| {
+                writeln!(f, "({values})")
+            })?;
+        }
+        _ => return Ok(None),
+    }
+    Ok(Some((params, body)))
+}
+
+fn write_nu_completes<'a, 'help>(
+    completions: &Completions<'_, '_>,
+    helpers: &mut Helpers,
+    app: &'a Command<'help>,
+    f: &mut dyn fmt::Write,
+    globals: &[&'a Arg<'help>],
+    name: FullCommandName<'_>,
+) -> fmt::Result {
+    if app.is_hide_set() {
+        return Ok(());
+    }
 
-        let nu_type = nu_value_hint(arg.get_value_hint());
-        if nu_type != "string" {
+    let args = all_arguments(app, globals);
+    for &arg in &args {
+        if arg.is_hide_set() {
             continue;
         }
 
-        let id = arg.get_id();
-        let value_matcher = arg.get_value_parser();
+        // Args with the same static completions share one helper.
+        if let Some((params, body)) = static_completer(completions, name, arg)? {
+            let helper = helpers.name(name, arg);
+            if helpers.written.insert(helper.clone()) {
+                writeln!(f, "def '{helper}' [{params}] {{")?;
+                write!(f, "{body}")?;
+                writeln!(f, "}}")?;
+                writeln!(f)?;
+            }
+            continue;
+        }
 
-        if let Some(possible_values) = value_matcher.possible_values() {
-            writeln!(f, "def 'nu-complete {name} --{id}' [] {{")?;
-            write!(f, "  [ ")?;
-            for possible_value in possible_values {
-                write!(
+        let case_sensitive = !arg.is_ignore_case_set();
+        match value_completer(completions, name, arg) {
+            Some(ValueCompleter::Dynamic) => {
+                writeln!(
                     f,
-                    "\"{}\", ",
-                    possible_value.get_name().replace('\"', "\\\"")
+                    "def '{}' [context: string] {{",
+                    nu_complete_name(name, arg)
                 )?;
+                if require_equals(arg) {
+                    writeln!(f, "  {REQUIRE_EQUALS}")?;
+                }
+                write_completer_body(completions, case_sensitive, f, |f, _| {
+                    write!(f, "(^")?;
+                    for word in completions.invocation_words() {
+                        write!(f, "{} ", quote_name(word))?;
+                    }
+                    write!(f, "{COMPLETE_COMMAND}")?;
+                    for name in name.path() {
+                        write!(f, " {}", quote_name(name))?;
+                    }
+                    write!(f, " {}", quote_name(arg.get_id()))?;
+                    writeln!(f, " ({CURRENT_VALUE}) | from json)")
+                })?;
+                writeln!(f, "}}")?;
+                writeln!(f)?;
             }
-            writeln!(f, "]")?;
-            writeln!(f, "}}")?;
-            writeln!(f)?;
+            Some(ValueCompleter::Command) => {
+                write_command_completer(app, &args, f, name, arg)?;
+            }
+            _ => {}
         }
     }
 
+    if app.is_allow_external_subcommands_set() {
+        write_external_subcommands_completer(completions, app, f, name)?;
+    }
+
+    let help =
+        (app.get_subcommands()).find(|help| help.get_name() == "help" && !help.is_hide_set());
+    let help_path = help.and_then(|help| {
+        let arg = help.get_arguments().find(|arg| is_help_path("help", arg))?;
+        Some((help, arg))
+    });
+    if let Some((help, arg)) = help_path {
+        let help_name = FullCommandName {
+            name: help.get_name(),
+            parent: Some(&name),
+        };
+        write_help_path_completer(completions, app, f, help_name, arg)?;
+    }
+
+    let globals: Vec<_> = args.into_iter().filter(|arg| arg.is_global_set()).collect();
     for app in app.get_subcommands() {
         let parent = Some(&name);
         let name = app.get_name();
-        write_nu_completes(app, f, FullCommandName { name, parent })?;
+        write_nu_completes(
+            completions,
+            helpers,
+            app,
+            f,
+            &globals,
+            FullCommandName { name, parent },
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Write the completer for `arg`, a positional taking a command line, which
+/// completes the command's name from `PATH` and delegates the completion of
+/// its arguments to the external completer configured in nushell, if any,
+/// as if the command had been typed at the prompt.
+///
+/// The command line starts after the command words and options and the
+/// positionals preceding `arg`, or after `--`.
+fn write_command_completer<'help>(
+    app: &Command<'help>,
+    args: &[&Arg<'help>],
+    f: &mut dyn fmt::Write,
+    name: FullCommandName<'_>,
+    arg: &Arg<'_>,
+) -> fmt::Result {
+    let preceding = (nu_positionals(app, args).iter())
+        .take_while(|positional| positional.arg.get_id() != arg.get_id())
+        .count();
+    let mut options_with_values = vec![];
+    for option in args {
+        if option.is_positional() || !option.is_takes_value_set() {
+            continue;
+        }
+        let longs = (option.get_long().into_iter())
+            .chain(option.get_all_aliases().unwrap_or_default())
+            .map(|long| format!("--{long}"));
+        let shorts = (option.get_short().into_iter())
+            .chain(option.get_all_short_aliases().unwrap_or_default())
+            .map(|short| format!("-{short}"));
+        options_with_values.extend(longs.chain(shorts).map(|option| quote(&option)));
     }
 
+    writeln!(
+        f,
+        "def '{}' [context: string] {{",
+        nu_complete_name(name, arg)
+    )?;
+    let words = name.words().len();
+    writeln!(f, "  mut words = ($context | split row ' ' | skip {words})")?;
+    writeln!(f, "  mut positionals = {preceding}")?;
+    writeln!(f, "  while ($words | length) > 1 {{")?;
+    writeln!(f, "    let word = ($words | first)")?;
+    writeln!(f, "    if $word == '--' {{")?;
+    writeln!(f, "      $words = ($words | skip 1)")?;
+    writeln!(f, "      break")?;
+    writeln!(f, "    }} else if ($word | str starts-with '-') {{")?;
+    writeln!(
+        f,
+        "      $words = ($words | skip (if $word in [{}] {{ 2 }} else {{ 1 }}))",
+        options_with_values.join(", ")
+    )?;
+    writeln!(f, "    }} else if $positionals > 0 {{")?;
+    writeln!(f, "      $words = ($words | skip 1)")?;
+    writeln!(f, "      $positionals = $positionals - 1")?;
+    writeln!(f, "    }} else {{")?;
+    writeln!(f, "      break")?;
+    writeln!(f, "    }}")?;
+    writeln!(f, "  }}")?;
+    writeln!(f, "  if ($words | length) <= 1 {{")?;
+    writeln!(f, "    return ({EXECUTABLES})")?;
+    writeln!(f, "  }}")?;
+    writeln!(
+        f,
+        "  let completer = $env.config.completions?.external?.completer?"
+    )?;
+    writeln!(f, "  if $completer == null {{")?;
+    writeln!(f, "    return null")?;
+    writeln!(f, "  }}")?;
+    writeln!(f, "  do $completer $words")?;
+    writeln!(f, "}}")?;
+    writeln!(f)?;
     Ok(())
 }
 
-fn write_export_externs(
+/// Write the completer for `arg`, the subcommand path taken by the `help`
+/// subcommand of `app`, which offers the subcommands of the subcommand at
+/// the path typed so far.
+fn write_help_path_completer(
+    completions: &Completions<'_, '_>,
     app: &Command<'_>,
+    f: &mut dyn fmt::Write,
+    name: FullCommandName<'_>,
+    arg: &Arg<'_>,
+) -> fmt::Result {
+    fn subcommands(app: &Command<'_>, parent: &str, entries: &mut Vec<String>) {
+        for subcommand in app.get_subcommands().filter(|app| !app.is_hide_set()) {
+            let description = quote(subcommand.get_about().map_or("", comment));
+            let mut names = vec![subcommand.get_name().to_string()];
+            names.extend(subcommand.get_visible_aliases().map(String::from));
+            for name in names {
+                entries.push(format!(
+                    "{{ parent: {}, value: {}, description: {description} }}",
+                    quote(parent),
+                    quote(&name),
+                ));
+                // `help` finds subcommands by their aliases too.
+                let path = match parent {
+                    "" => name.to_string(),
+                    parent => format!("{parent} {name}"),
+                };
+                subcommands(subcommand, &path, entries);
+            }
+        }
+    }
+    let mut entries = vec![];
+    subcommands(app, "", &mut entries);
+
+    // The subcommand path typed after `help`, without the word being completed.
+    let words = name.words().len();
+    writeln!(
+        f,
+        "def '{}' [context: string] {{",
+        nu_complete_name(name, arg)
+    )?;
+    writeln!(
+        f,
+        "  let parent = ($context | split row ' ' | skip {words} | drop 1 | str join ' ')"
+    )?;
+    write_completer_body(completions, true, f, |f, indent| {
+        writeln!(f, "([")?;
+        for entry in entries {
+            writeln!(f, "{indent}  {entry},")?;
+        }
+        writeln!(
+            f,
+            "{indent}] | where parent == $parent | select value description)"
+        )
+    })?;
+    writeln!(f, "}}")?;
+    writeln!(f)
+}
+
+/// The prefix of the names of executables providing external subcommands of
+/// the command `name`.
+fn external_subcommand_prefix(
+    completions: &Completions<'_, '_>,
+    name: FullCommandName<'_>,
+) -> String {
+    let mut prefix = match completions.nu.external_subcommands.prefix {
+        Some(prefix) => prefix.to_string(),
+        None => format!("{}-", completions.invocation_words().join("-")),
+    };
+    for name in name.path() {
+        prefix.push_str(name);
+        prefix.push('-');
+    }
+    prefix
+}
+
+/// Write the completer for the subcommands of `app`, listing its built-in
+/// subcommands followed by external subcommands discovered on `PATH`.
+fn write_external_subcommands_completer(
+    completions: &Completions<'_, '_>,
+    app: &Command<'_>,
+    f: &mut dyn fmt::Write,
+    name: FullCommandName<'_>,
+) -> fmt::Result {
+    let prefix = external_subcommand_prefix(completions, name);
+    let description = match completions.nu.external_subcommands.description {
+        ExternalSubcommandDescription::None => "\"\"".to_string(),
+        ExternalSubcommandDescription::Path => "$path".to_string(),
+        ExternalSubcommandDescription::Output(args) => {
+            let args: Vec<_> = args.iter().map(|arg| quote(arg)).collect();
+            format!(
+                "(^$path {} | complete | get stdout | lines | append '' | first)",
+                args.join(" ")
+            )
+        }
+    };
+
+    let mut builtin = vec![];
+    for subcommand in app.get_subcommands().filter(|app| !app.is_hide_set()) {
+        let description = quote(subcommand.get_about().map_or("", comment));
+        builtin.push((quote(subcommand.get_name()), description.clone()));
+        for alias in subcommand.get_visible_aliases() {
+            builtin.push((quote(alias), description.clone()));
+        }
+    }
+
+    writeln!(f, "def '{}' [] {{", nu_complete_subcommands_name(name))?;
+    write_completer_body(completions, true, f, |f, indent| {
+        writeln!(f, "([")?;
+        for (value, description) in builtin {
+            writeln!(
+                f,
+                "{indent}  {{ value: {value}, description: {description} }},"
+            )?;
+        }
+        write!(f, "{indent}] | append (")?;
+        write!(f, "$env.PATH? | default ($env.Path? | default [])")?;
+        write!(f, " | where ($it | path exists)")?;
+        write!(
+            f,
+            " | each {{|dir| ls $dir | where type != dir | each {{|entry| $entry.name }} }}"
+        )?;
+        write!(f, " | flatten")?;
+        write!(
+            f,
+            " | where ($it | path basename | str starts-with {})",
+            quote(&prefix)
+        )?;
+        write!(
+            f,
+            " | each {{|path| {{ value: ($path | path basename | str substring {}..),",
+            prefix.len()
+        )?;
+        writeln!(f, " description: {description} }} }}))")
+    })?;
+    writeln!(f, "}}")?;
+    writeln!(f)
+}
+
+/// Write the body of a completer, returning the completions
+/// written by `write_completions` at the given indentation, along with
+/// completion options where supported.
+fn write_completer_body(
+    completions: &Completions<'_, '_>,
+    case_sensitive: bool,
+    f: &mut dyn fmt::Write,
+    write_completions: impl FnOnce(&mut dyn fmt::Write, &str) -> fmt::Result,
+) -> fmt::Result {
+    if completions.nu.version < NuVersion::COMPLETION_OPTIONS {
+        write!(f, "  ")?;
+        return write_completions(f, "  ");
+    }
+
+    // Keep clap's order, rather than sorting completions alphabetically.
+    writeln!(f, "  {{")?;
+    write!(f, "    options: {{ sort: false")?;
+    write!(f, ", case_sensitive: {case_sensitive}")?;
+    if let Some(algorithm) = completions.nu.completion_algorithm {
+        write!(f, ", completion_algorithm: {}", quote(algorithm.name()))?;
+    }
+    writeln!(f, " }}")?;
+    write!(f, "    completions: ")?;
+    write_completions(f, "    ")?;
+    writeln!(f, "  }}")
+}
+
+fn write_possible_values(
+    f: &mut dyn fmt::Write,
+    indent: &str,
+    records: bool,
+    possible_values: Vec<PossibleValue<'_>>,
+) -> fmt::Result {
+    let possible_values: Vec<_> = (possible_values.into_iter())
+        .filter(|pv| !pv.is_hide_set())
+        .collect();
+    if records || possible_values.iter().any(|pv| pv.get_help().is_some()) {
+        writeln!(f, "[")?;
+        for possible_value in possible_values {
+            let value = quote(possible_value.get_name());
+            let description = quote(possible_value.get_help().unwrap_or(""));
+            writeln!(
+                f,
+                "{indent}  {{ value: {value}, description: {description} }},"
+            )?;
+        }
+        writeln!(f, "{indent}]")?;
+    } else {
+        write!(f, "[ ")?;
+        for possible_value in possible_values {
+            write!(f, "{}, ", quote(possible_value.get_name()))?;
+        }
+        writeln!(f, "]")?;
+    }
+    Ok(())
+}
+
+/// Whether `arg`'s values can only be given as `--flag=value`.
+fn require_equals(arg: &Arg<'_>) -> bool {
+    arg.is_require_equals_set() && !arg.is_positional()
+}
+
+/// The delimiter `arg` splits its values on, if any.
+fn value_delimiter(arg: &Arg<'_>) -> Option<char> {
+    (arg.get_value_delimiter()).filter(|_| arg.is_use_value_delimiter_set())
+}
+
+/// Write the body of a completer for the last element of a list of values
+/// separated by `delimiter`, which offers the possible values not yet in the
+/// list, prefixed with the elements typed before it.
+fn write_delimited_values(
+    completions: &Completions<'_, '_>,
+    case_sensitive: bool,
+    f: &mut dyn fmt::Write,
+    delimiter: char,
+    possible_values: Vec<PossibleValue<'_>>,
+) -> fmt::Result {
+    let delimiter = quote(&delimiter.to_string());
+    writeln!(
+        f,
+        "  let values = ({CURRENT_VALUE} | split row {delimiter} | drop 1)"
+    )?;
+    writeln!(
+        f,
+        "  let prefix = ($values | each {{|value| $value + {delimiter} }} | str join)"
+    )?;
+    write_completer_body(completions, case_sensitive, f, |f, indent| {
+        write!(f, "(")?;
+        write_possible_values(f, indent, true, possible_values)?;
+        writeln!(f, "{indent}| where value not-in $values")?;
+        writeln!(f, "{indent}| update value {{|pv| $prefix + $pv.value }})")
+    })
+}
+
+/// Every visible name a subcommand can be invoked by, canonical name first.
+fn subcommand_names(app: &Command<'_>) -> Vec<String> {
+    let short_flags = (app.get_short_flag().into_iter())
+        .chain(app.get_visible_short_flag_aliases())
+        .map(|short| format!("-{short}"));
+    let long_flags = (app.get_long_flag().into_iter())
+        .chain(app.get_visible_long_flag_aliases())
+        .map(|long| format!("--{long}"));
+
+    let mut names = vec![app.get_name().to_string()];
+    names.extend(app.get_visible_aliases().map(String::from));
+    names.extend(short_flags);
+    names.extend(long_flags);
+    names
+}
+
+/// `name` is the canonical command path used to refer to `nu-complete`
+/// helpers, while `extern_name` is the (possibly aliased) invocation.
+fn write_export_externs<'a, 'help>(
+    completions: &Completions<'_, '_>,
+    helpers: &Helpers,
+    app: &'a Command<'help>,
     f: &mut fmt::Formatter<'_>,
+    globals: &[&'a Arg<'help>],
     name: FullCommandName<'_>,
+    extern_name: FullCommandName<'_>,
 ) -> fmt::Result {
     if app.is_hide_set() {
         return Ok(());
     }
 
-    if let Some(about) = app.get_about() {
-        let about = about.split(['\r', '\n']).next().unwrap();
-        writeln!(f, "# {about}")?;
+    // Examples in the after help are written as `@example` attributes where
+    // supported, and registered examples replace them.
+    let attributes = completions.nu.version >= NuVersion::ATTRIBUTES;
+    let after_help = (app.get_after_long_help())
+        .or_else(|| app.get_after_help())
+        .unwrap_or("");
+    let registered: Vec<_> = match completions.nu.examples {
+        Some(examples) => examples.get(&name.path()).cloned().collect(),
+        None => vec![],
+    };
+    let (mut after_help, mut examples) = match attributes || !registered.is_empty() {
+        true => {
+            let bin = completions.invocation_words().first().copied();
+            extract_examples(after_help, bin.unwrap_or(""))
+        }
+        false => (after_help.to_string(), vec![]),
+    };
+    if !registered.is_empty() {
+        examples = registered;
+    }
+    if !attributes && !examples.is_empty() {
+        after_help = [after_help, examples_section(&examples)].join("\n\n");
+        after_help = after_help.trim().to_string();
+        examples.clear();
+    }
+
+    // Everything `--help` shows about the command itself.
+    let docs: Vec<_> = [
+        app.get_long_about().or_else(|| app.get_about()),
+        app.get_before_long_help().or_else(|| app.get_before_help()),
+        Some(&*after_help),
+    ]
+    .into_iter()
+    .flatten()
+    .map(str::trim)
+    .filter(|docs| !docs.is_empty())
+    .collect();
+    write_doc_comment(f, &docs.join("\n\n"))?;
+
+    for example in &examples {
+        let description = quote(example.get_description());
+        let invocation = quote(example.get_invocation());
+        match example.get_result() {
+            Some(result) => writeln!(
+                f,
+                "@example {description} {invocation} --result {}",
+                quote(result)
+            )?,
+            None => writeln!(f, "@example {description} {invocation}")?,
+        }
     }
 
-    writeln!(f, "export extern '{name}' [")?;
+    writeln!(
+        f,
+        "export extern {} [",
+        quote_name(&extern_name.to_string())
+    )?;
 
-    let mut write_param = |arg: &Arg<'_>| -> fmt::Result {
-        let id = arg.get_id();
-        let help = arg.get_help();
-        let long = arg
-            .get_long()
-            .or_else(|| arg.get_long_and_visible_aliases().map(|v| v[0]));
-        let short = arg
-            .get_short()
-            .or_else(|| arg.get_short_and_visible_aliases().map(|v| v[0]));
-        let value_hint = arg.get_value_hint();
-        let required = arg.is_required_set();
+    let mut write_param = |arg: &Arg<'_>, positional: Option<&Positional<'_, '_>>| -> fmt::Result {
+        let help = (arg.get_long_help())
+            .or_else(|| arg.get_help())
+            .map_or("", str::trim);
+        let details = param_doc_comment(help);
+        let long = arg.get_long();
+        let short = arg.get_short();
+        let long_aliases = arg.get_visible_aliases().unwrap_or_default();
+        let short_aliases = arg.get_visible_short_aliases().unwrap_or_default();
         let takes_value = arg.is_takes_value_set();
-        let takes_many = arg.is_multiple_values_set();
+        let takes_many = match arg.get_num_vals() {
+            Some(num_vals) => num_vals > 1,
+            None => arg.is_multiple_values_set(),
+        };
+        let repeatable = arg.is_multiple_occurrences_set()
+            || matches!(arg.get_action(), ArgAction::Count | ArgAction::Append);
 
-        let value_matcher = arg.get_value_parser();
-        let has_value_completion = value_matcher.possible_values().is_some();
+        let has_value_completion = value_completer(completions, name, arg).is_some();
 
-        let splat = if takes_many { "..." } else { "" };
-        let nu_type = nu_value_hint(value_hint);
+        let nu_type = nu_type(completions, arg);
         let nu_type = if has_value_completion {
-            format!("{nu_type}@'nu-complete {name} --{id}'")
+            let helper = helpers.name(name, arg);
+            format!("{nu_type}@'{helper}'")
         } else {
             nu_type.to_string()
         };
-        let nu_optional = if required { "" } else { "?" };
-        let help = help.unwrap_or("").split(['\r', '\n']).next().unwrap();
-
-        match (long, short, takes_value) {
-            (Some(long), Some(short), true) => {
-                writeln!(f, "  --{long}(-{short}): {nu_type} # {help}")?;
+        let help = comment(help);
+        // Value names are part of the parameter name where possible, and are
+        // otherwise shown like in clap's help.
+        let label = match positional {
+            Some(positional)
+                if matches!(arg.get_value_names(), Some([_]))
+                    && positional.name == nu_param_name(arg) =>
+            {
+                None
             }
-            (Some(long), Some(short), false) => {
-                writeln!(f, "  --{long}(-{short}) # {help}")?;
+            _ => value_label(arg),
+        };
+        let help = match label {
+            Some(label) if help.is_empty() => label,
+            Some(label) => format!("{label} {help}"),
+            None => help.to_string(),
+        };
+        // nushell flags take a single value, so how many values clap takes
+        // is noted; any further values are left to positionals.
+        let occurrences = match takes_many {
+            _ if arg.is_positional() => None,
+            true if value_delimiter(arg).is_some() => {
+                let delimiter = value_delimiter(arg).unwrap_or(',');
+                Some(format!("multiple values separated by '{delimiter}'"))
             }
-            (Some(long), None, true) => {
-                writeln!(f, "  --{long}: {nu_type} # {help}")?;
+            true if matches!(arg.get_num_vals(), Some(num_vals) if num_vals > 1) => {
+                let num_vals = arg.get_num_vals().unwrap_or_default();
+                Some(format!("takes {num_vals} values"))
             }
-            (Some(long), None, false) => {
-                writeln!(f, "  --{long} # {help}")?;
+            true => Some("takes multiple values".to_string()),
+            false => None,
+        };
+        let repeated = (repeatable && !arg.is_positional()).then(|| "may be repeated".to_string());
+        // nushell accepts `--flag=value` for extern flags, but cannot reject
+        // the `--flag value` form clap refuses for such args.
+        let label = value_label(arg).unwrap_or_else(|| format!("<{}>", arg.get_id()));
+        let equals = match (long, short) {
+            _ if !arg.is_require_equals_set() || !takes_value => None,
+            (Some(long), _) => Some(format!("written as --{long}={label}")),
+            (None, Some(short)) => Some(format!(
+                "written as -{short}={label}, which nushell cannot pass"
+            )),
+            (None, None) => None,
+        };
+        let notes: Vec<_> = (occurrences.into_iter())
+            .chain(repeated)
+            .chain(equals)
+            .collect();
+        let help = match notes.join("; ") {
+            notes if notes.is_empty() => help,
+            notes if help.is_empty() => format!("({notes})"),
+            notes => format!("{help} ({notes})"),
+        };
+
+        if let Some(positional) = positional {
+            // Rest parameters are always optional in nu.
+            let (splat, nu_optional) = match (positional.rest, positional.required) {
+                (true, _) => ("...", ""),
+                (false, true) => ("", ""),
+                (false, false) => ("", "?"),
+            };
+            let mut notes = Vec::new();
+            if positional.rest && positional.required {
+                notes.push("at least one required".to_string());
             }
-            (None, Some(short), true) => {
-                writeln!(f, "  -{short}: {nu_type} # {help}")?;
+            if arg.is_last_set() {
+                notes.push("after --".to_string());
+            } else if arg.is_allow_hyphen_values_set() {
+                // nushell takes words starting with `-` to be flags.
+                notes.push("values starting with - after --".to_string());
             }
-            (None, Some(short), false) => {
-                writeln!(f, "  -{short} # {help}")?;
+            if !positional.folded.is_empty() {
+                let folded: Vec<_> = (positional.folded.iter())
+                    .map(|arg| match arg.is_last_set() {
+                        true => format!("{} after --", nu_param_name(arg)),
+                        false => nu_param_name(arg),
+                    })
+                    .collect();
+                notes.push(format!("followed by {}", folded.join(", ")));
             }
-            (None, None, _) => {
-                writeln!(f, "  {splat}{id}{nu_optional}: {nu_type} # {help}")?;
+            let help = match notes.join("; ") {
+                notes if notes.is_empty() => help,
+                notes if help.is_empty() => format!("({notes})"),
+                notes => format!("{help} ({notes})"),
+            };
+            let name = &positional.name;
+            return writeln!(
+                f,
+                "  {splat}{name}{nu_optional}: {nu_type} # {help}{details}"
+            );
+        }
+
+        let mut write_flag = |long: Option<&str>, short: Option<char>| -> fmt::Result {
+            let long = long.filter(|long| is_nu_flag(long));
+            let short = short.filter(|short| is_nu_flag(short.encode_utf8(&mut [0; 4])));
+            match (long, short, takes_value) {
+                (Some(long), Some(short), true) => {
+                    writeln!(f, "  --{long}(-{short}): {nu_type} # {help}{details}")
+                }
+                (Some(long), Some(short), false) => {
+                    writeln!(f, "  --{long}(-{short}) # {help}{details}")
+                }
+                (Some(long), None, true) => {
+                    writeln!(f, "  --{long}: {nu_type} # {help}{details}")
+                }
+                (Some(long), None, false) => {
+                    writeln!(f, "  --{long} # {help}{details}")
+                }
+                (None, Some(short), true) => {
+                    writeln!(f, "  -{short}: {nu_type} # {help}{details}")
+                }
+                (None, Some(short), false) => {
+                    writeln!(f, "  -{short} # {help}{details}")
+                }
+                (None, None, _) => Ok(()),
             }
         };
 
+        write_flag(long, short)?;
+
+        // nushell flags have at most one long and one short name, so visible
+        // aliases are declared as additional flags sharing the same signature.
+        for long in long_aliases {
+            write_flag(Some(long), None)?;
+        }
+        for short in short_aliases {
+            write_flag(None, Some(short))?;
+        }
+
         Ok(())
     };
 
-    app.get_arguments()
-        .filter(|arg| !arg.is_hide_set())
-        .filter(|arg| arg.is_positional())
-        .try_for_each(&mut write_param)?;
-    app.get_arguments()
+    let args = all_arguments(app, globals);
+    let positionals = nu_positionals(app, &args);
+    for positional in &positionals {
+        write_param(positional.arg, Some(positional))?;
+    }
+    (args.iter().copied())
         .filter(|arg| !arg.is_hide_set())
         .filter(|arg| !arg.is_positional())
-        .try_for_each(&mut write_param)?;
+        .try_for_each(|arg| write_param(arg, None))?;
+
+    // Values after the first of a flag taking several are parsed as
+    // positionals, so a rest parameter takes them where nothing else would.
+    let flag_variables: Vec<_> = (args.iter())
+        .filter(|arg| !arg.is_positional())
+        .map(|arg| nu_identifier(arg.get_long().unwrap_or(arg.get_id())).replace('-', "_"))
+        .collect();
+    let takes_many: Vec<_> = (args.iter())
+        .filter(|arg| !arg.is_hide_set() && !arg.is_positional())
+        .filter(|arg| match arg.get_num_vals() {
+            Some(num_vals) => num_vals > 1,
+            None => arg.is_multiple_values_set(),
+        })
+        .filter_map(|arg| {
+            let long = arg.get_long().filter(|long| is_nu_flag(long));
+            let short =
+                (arg.get_short()).filter(|short| is_nu_flag(short.encode_utf8(&mut [0; 4])));
+            (long.map(|long| format!("--{long}")))
+                .or_else(|| short.map(|short| format!("-{short}")))
+        })
+        .collect();
+    if !takes_many.is_empty() && positionals.is_empty() && !app.is_allow_external_subcommands_set()
+    {
+        let mut name = String::from("values");
+        while flag_variables.contains(&name) {
+            name.insert(0, '_');
+        }
+        writeln!(
+            f,
+            "  ...{name}: string # further values of {}",
+            takes_many.join(", ")
+        )?;
+    }
+
+    // External subcommands follow any positionals, unless those already
+    // include a rest parameter.
+    if app.is_allow_external_subcommands_set() && !positionals.iter().any(|p| p.rest) {
+        let helper = nu_complete_subcommands_name(name);
+        writeln!(
+            f,
+            "  external_subcommand?: string@'{helper}' # external subcommand"
+        )?;
+        writeln!(
+            f,
+            "  ...external_args: string # arguments to the external subcommand"
+        )?;
+    }
 
     writeln!(f, "]")?;
     writeln!(f)?;
 
+    let globals: Vec<_> = args.into_iter().filter(|arg| arg.is_global_set()).collect();
     for app in app.get_subcommands() {
-        let parent = Some(&name);
-        let name = app.get_name();
-        write_export_externs(app, f, FullCommandName { name, parent })?;
+        let name = FullCommandName {
+            name: app.get_name(),
+            parent: Some(&name),
+        };
+        for alias in subcommand_names(app) {
+            let extern_name = FullCommandName {
+                name: &alias,
+                parent: Some(&extern_name),
+            };
+            write_export_externs(completions, helpers, app, f, &globals, name, extern_name)?;
+        }
     }
 
     Ok(())
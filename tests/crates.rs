@@ -6,13 +6,13 @@ use shared::*;
 macro_rules! tests {
     ($name:literal, $app:ident) => {
         #[cfg(feature = "nu")]
-        insta::assert_display_snapshot!(
+        insta::assert_snapshot!(
             concat!($name, "/nu"),
-            clap_completions::nu::Completions::new(&$app)
+            clap_completions::nu::Completions::new(&$app).to_string()
         );
 
         #[cfg(feature = "nu")]
-        if let Ok(help) = get_nu_help(&$app) {
+        if let Some(help) = get_nu_help(&$app) {
             insta::assert_snapshot!(concat!($name, "/nu/help"), help);
         }
 
@@ -103,7 +103,7 @@ fn bindgen() {
                 .help("The default style of code used to generate enums.")
                 .value_name("variant")
                 .default_value("consts")
-                .possible_values(&[
+                .possible_values([
                     "consts",
                     "moduleconsts",
                     "bitfield",
@@ -156,14 +156,14 @@ fn bindgen() {
                 .help("The default signed/unsigned type for C macro constants.")
                 .value_name("variant")
                 .default_value("unsigned")
-                .possible_values(&["signed", "unsigned"])
+                .possible_values(["signed", "unsigned"])
                 .multiple_occurrences(false),
             Arg::new("default-alias-style")
                 .long("default-alias-style")
                 .help("The default style of code used to generate typedefs.")
                 .value_name("variant")
                 .default_value("type_alias")
-                .possible_values(&[
+                .possible_values([
                     "type_alias",
                     "new_type",
                     "new_type_deref",
@@ -857,7 +857,7 @@ pub fn multi_opt(name: &'static str, value_name: &'static str, help: &'static st
         .action(ArgAction::Append)
 }
 
-pub fn subcommand(name: &'static str) -> App {
+pub fn subcommand(name: &'static str) -> App<'static> {
     App::new(name)
         .dont_collapse_args_in_usage(true)
         .setting(AppSettings::DeriveDisplayOrder)
@@ -866,6 +866,7 @@ pub fn subcommand(name: &'static str) -> App {
 #[test]
 fn cargo_add() {
     let mut app = clap::Command::new("add")
+            .bin_name("cargo add")
             .setting(clap::AppSettings::DeriveDisplayOrder)
             .about("Add dependencies to a Cargo.toml manifest file")
             .override_usage(
@@ -0,0 +1,78 @@
+use clap_completions::dynamic::{Candidate, Completers, COMPLETE_COMMAND};
+
+fn completers() -> Completers {
+    Completers::new()
+        .register(&[], "host", |current| {
+            ["alpha", "beta"]
+                .into_iter()
+                .filter(|host| host.starts_with(current))
+                .collect::<Vec<_>>()
+        })
+        .register(&["remote", "add"], "name", |_| {
+            [Candidate::new("origin").help("the default remote")]
+        })
+}
+
+#[test]
+fn dispatch() {
+    let completers = completers();
+
+    assert_eq!(
+        completers.complete(["my-app", COMPLETE_COMMAND, "host", "b"]),
+        Some(vec![Candidate::new("beta")]),
+    );
+    assert_eq!(
+        completers.complete(["my-app", COMPLETE_COMMAND, "host", ""]),
+        Some(vec![Candidate::new("alpha"), Candidate::new("beta")]),
+    );
+    assert_eq!(
+        completers.complete(["my-app", COMPLETE_COMMAND, "remote", "add", "name", ""]),
+        Some(vec![Candidate::new("origin").help("the default remote")]),
+    );
+}
+
+#[test]
+fn multi_word_invocation() {
+    let completers = completers();
+
+    assert_eq!(
+        completers.complete(["cargo-my-app", "my-app", COMPLETE_COMMAND, "host", "a"]),
+        None,
+    );
+
+    let completers = completers.invocation(&["cargo", "my-app"]);
+    assert_eq!(
+        completers.complete(["cargo-my-app", "my-app", COMPLETE_COMMAND, "host", "a"]),
+        Some(vec![Candidate::new("alpha")]),
+    );
+    assert_eq!(
+        completers.complete(["cargo-my-app", COMPLETE_COMMAND, "host", "a"]),
+        None,
+    );
+}
+
+#[test]
+fn not_requested() {
+    let completers = completers();
+
+    assert_eq!(completers.complete(["my-app", "host", "b"]), None);
+    assert_eq!(completers.complete(["my-app", COMPLETE_COMMAND, "b"]), None);
+    assert_eq!(
+        completers.complete(["my-app", COMPLETE_COMMAND, "remote", "host", ""]),
+        None,
+    );
+}
+
+#[test]
+fn ordinary_argument() {
+    let completers = completers();
+
+    assert_eq!(
+        completers.complete(["my-app", "grep", COMPLETE_COMMAND, "host", ""]),
+        None,
+    );
+    assert_eq!(
+        completers.complete(["my-app", "host", COMPLETE_COMMAND]),
+        None,
+    );
+}
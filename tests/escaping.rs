@@ -0,0 +1,198 @@
+//! Generate completions for pseudo-random command trees built from hostile
+//! names and text, and check that the output is a valid nushell module.
+
+#![cfg(feature = "nu")]
+
+use clap::{builder::PossibleValuesParser, Arg, Command, PossibleValue};
+use clap_completions::nu;
+use std::collections::BTreeSet;
+
+#[allow(dead_code)]
+mod shared;
+use shared::*;
+
+const TEXT: &[&str] = &[
+    "plain",
+    "it's",
+    "say \"hi\"",
+    "hash#tag",
+    "[bracket]",
+    "{brace}",
+    "(paren)",
+    "back\\slash",
+    "trailing\\",
+    "$HOME",
+    "`tick`",
+    "semi;colon",
+    "pipe|line",
+    "new\nline",
+    "carriage\rreturn",
+    "tab\there",
+    "bell\u{7}",
+    "ünïcödé",
+    "🦀",
+    "a b c",
+    "--dashes",
+    "-",
+    "",
+];
+
+const SHORTS: &[char] = &['a', 'Z', '#', '\'', '"', '[', 'é', '🦀', '?', '1'];
+
+/// A tiny deterministic xorshift generator, so failures are reproducible.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 as usize
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        self.next() % n
+    }
+
+    fn chance(&mut self) -> bool {
+        self.below(2) == 0
+    }
+
+    fn text(&mut self) -> &'static str {
+        TEXT[self.below(TEXT.len())]
+    }
+
+    /// A hostile name which is unique by virtue of its `n` suffix.
+    fn name(&mut self, n: usize) -> &'static str {
+        Box::leak(format!("{}{n}", self.text()).into_boxed_str())
+    }
+}
+
+fn arbitrary_arg(rng: &mut Rng, n: usize) -> Arg<'static> {
+    let mut arg = Arg::new(rng.name(n)).help(rng.text());
+    if rng.chance() {
+        arg = arg.long(rng.name(n));
+        if rng.chance() {
+            arg = arg.visible_alias(rng.name(n + 100));
+        }
+    }
+    if rng.chance() {
+        arg = arg.short(SHORTS[n % SHORTS.len()]);
+    }
+    if rng.chance() {
+        arg = arg.takes_value(true);
+        if rng.chance() {
+            let values = (0..rng.below(4)).map(|i| {
+                let value = PossibleValue::new(rng.name(i));
+                if rng.chance() {
+                    value.help(rng.text())
+                } else {
+                    value
+                }
+            });
+            let values: Vec<_> = values.collect();
+            arg = arg.value_parser(PossibleValuesParser::new(values));
+        }
+    }
+    arg
+}
+
+fn arbitrary_command(rng: &mut Rng, name: &'static str, depth: usize) -> Command<'static> {
+    let mut app = Command::new(name).about(rng.text());
+    for n in 0..rng.below(5) {
+        app = app.arg(arbitrary_arg(rng, n));
+    }
+    if depth > 0 {
+        for n in 0..rng.below(4) {
+            let name = rng.name(n);
+            let mut sub = arbitrary_command(rng, name, depth - 1);
+            if rng.chance() {
+                sub = sub.visible_alias(rng.name(n + 100));
+            }
+            app = app.subcommand(sub);
+        }
+    }
+    app
+}
+
+#[test]
+fn arbitrary_command_trees() {
+    for seed in 1..=200 {
+        let app = arbitrary_command(&mut Rng(seed), "my-app", 2);
+        let module = nu::Completions::new(&app).to_string();
+
+        // Parse the module as generated for the installed nushell.
+        if let Some(version) = nu_version() {
+            run_nu(&nu::Completions::new(&app).version(version).to_string(), "");
+        }
+
+        let defined: BTreeSet<_> = helpers(&module).into_iter().collect();
+        for completer in module.split("@'").skip(1) {
+            let completer = completer.split('\'').next().unwrap();
+            assert!(
+                defined.contains(completer),
+                "seed {seed}: undefined completer {completer:?} in\n{module}"
+            );
+        }
+    }
+}
+
+/// Ids and names which naive helper naming schemes would conflate.
+const PATHOLOGICAL: &[&str] = &[
+    "a", "a b", "a  b", "a\tb", "a_b", "a'b", "a%27b", "a%b", "--a", "-a", "a --b", "b", "%",
+    "%25", " ", "'", "_",
+];
+
+/// The names of the helpers defined by `module`.
+fn helpers(module: &str) -> Vec<&str> {
+    (module.lines())
+        .filter_map(|line| line.strip_prefix("def '")?.split('\'').next())
+        .collect()
+}
+
+#[test]
+fn helper_names_are_unique() {
+    // Values differ between all args, so that no helper is shared.
+    let arg = |parent: &'static str, name: &'static str, id: &'static str| {
+        Arg::new(id)
+            .takes_value(true)
+            .value_parser([parent, name, id])
+    };
+    let command = |parent: &'static str, name: &'static str| {
+        Command::new(name).args(PATHOLOGICAL.iter().map(|&id| arg(parent, name, id)))
+    };
+    let app = command("", "my-app")
+        .subcommands(PATHOLOGICAL.iter().map(|&name| command("my-app", name)))
+        .subcommand(command("my-app", "nested").subcommand(command("nested", "a b")));
+    let module = nu::Completions::new(&app).to_string();
+
+    let helpers = helpers(&module);
+    let unique: BTreeSet<_> = helpers.iter().collect();
+    assert_eq!(
+        helpers.len(),
+        unique.len(),
+        "duplicate helpers in\n{module}"
+    );
+    assert_eq!(helpers.len(), PATHOLOGICAL.len() * (PATHOLOGICAL.len() + 3));
+
+    // Names are stable for ordinary commands and ids.
+    assert!(helpers.contains(&"nu-complete my-app a --b"));
+    assert!(helpers.contains(&"nu-complete my-app nested a%20b --a%27b"));
+}
+
+#[test]
+fn identical_completions_share_a_helper() {
+    let arg = |id: &'static str| Arg::new(id).takes_value(true).value_parser(["x", "y"]);
+    let command =
+        |name: &'static str| Command::new(name).args(PATHOLOGICAL.iter().map(|&id| arg(id)));
+    let app = command("my-app").subcommands(PATHOLOGICAL.iter().map(|&name| command(name)));
+    let module = nu::Completions::new(&app).to_string();
+
+    let helpers = helpers(&module);
+    assert_eq!(helpers.len(), 1, "unshared helpers in\n{module}");
+    assert!(helpers[0].starts_with("nu-complete-values-"));
+    assert_eq!(
+        module.matches(&format!("string@'{}'", helpers[0])).count(),
+        PATHOLOGICAL.len() * (PATHOLOGICAL.len() + 1)
+    );
+}
@@ -8,9 +8,37 @@ macro_rules! all_tests {
         insta::assert_snapshot!(concat!($name, "/aliases"), $test!(aliases_command));
         insta::assert_snapshot!(concat!($name, "/sub-subcommands"), $test!(sub_subcommands_command));
         insta::assert_snapshot!(concat!($name, "/value-hint"), $test!(value_hint_command));
+        insta::assert_snapshot!(concat!($name, "/value-parser"), $test!(value_parser_command));
+        insta::assert_snapshot!(concat!($name, "/occurrences"), $test!(occurrences_command));
+        insta::assert_snapshot!(concat!($name, "/positionals"), $test!(positionals_command));
+        insta::assert_snapshot!(concat!($name, "/long-help"), $test!(long_help_command));
+        insta::assert_snapshot!(concat!($name, "/examples"), $test!(examples_command));
+        insta::assert_snapshot!(concat!($name, "/shared-values"), $test!(shared_values_command));
+        insta::assert_snapshot!(concat!($name, "/external-subcommands"), $test!(external_subcommands_command));
+        insta::assert_snapshot!(concat!($name, "/value-delimiter"), $test!(value_delimiter_command));
     };
 }
 
+/// The fixtures of `all_tests!`, for checks without snapshots.
+#[cfg(feature = "nu")]
+const FIXTURES: &[fn(&'static str) -> clap::Command<'static>] = &[
+    basic_command,
+    feature_sample_command,
+    special_commands_command,
+    quoting_command,
+    aliases_command,
+    sub_subcommands_command,
+    value_hint_command,
+    value_parser_command,
+    occurrences_command,
+    positionals_command,
+    long_help_command,
+    examples_command,
+    shared_values_command,
+    external_subcommands_command,
+    value_delimiter_command,
+];
+
 mod shared;
 use shared::*;
 
@@ -46,20 +74,238 @@ fn nu_completions() {
 
     all_tests!("nu", test);
 
+    // Before attributes were supported.
+    macro_rules! test_0_102 {
+        ($f:ident) => {
+            &{
+                let mut app = $f("my-app");
+                app.build();
+                nu::Completions::new(&app)
+                    .version(nu::NuVersion::new(0, 102))
+                    .to_string()
+            }
+        };
+    }
+
+    all_tests!("nu/0.102", test_0_102);
+
+    // Before completion options were supported.
+    macro_rules! test_0_97 {
+        ($f:ident) => {
+            &{
+                let mut app = $f("my-app");
+                app.build();
+                nu::Completions::new(&app)
+                    .version(nu::NuVersion::new(0, 97))
+                    .to_string()
+            }
+        };
+    }
+
+    all_tests!("nu/0.97", test_0_97);
+
+    insta::assert_snapshot!(
+        "nu/skip-value-hints",
+        nu::Completions::new(&value_hint_command("my-app"))
+            .skip_value_hints(&[clap::ValueHint::DirPath, clap::ValueHint::Hostname])
+            .to_string()
+    );
+
+    insta::assert_snapshot!(
+        "nu/external-subcommands-configured",
+        nu::Completions::new(&external_subcommands_command("my-app"))
+            .external_subcommands(
+                nu::ExternalSubcommands::new()
+                    .prefix("my-app-plugin-")
+                    .description(nu::ExternalSubcommandDescription::Output(&["--about"])),
+            )
+            .to_string()
+    );
+
+    insta::assert_snapshot!(
+        "nu/fuzzy",
+        nu::Completions::new(&value_parser_command("my-app"))
+            .completion_algorithm(nu::CompletionAlgorithm::Fuzzy)
+            .to_string()
+    );
+
+    // Global arguments must reach subcommands even if clap hasn't propagated them.
+    insta::assert_snapshot!(
+        "nu/basic-unbuilt",
+        nu::Completions::new(&basic_command("my-app")).to_string()
+    );
+
+    insta::assert_snapshot!(
+        "nu/bin-name",
+        nu::Completions::new(&basic_command("my-app").bin_name("git my-app")).to_string()
+    );
+
+    let completers =
+        clap_completions::dynamic::Completers::new().register(&[], "file", |_| ["dynamic"]);
+    insta::assert_snapshot!(
+        "nu/invocation",
+        nu::Completions::new(&feature_sample_command("cargo-my-app"))
+            .invocation(&["cargo", "my-app"])
+            .dynamic(&completers)
+            .to_string()
+    );
+
+    let completers = clap_completions::dynamic::Completers::new()
+        .register(&[], "file", |_| ["dynamic"])
+        .register(&["test"], "case", |_| ["dynamic"]);
+    insta::assert_snapshot!(
+        "nu/dynamic",
+        nu::Completions::new(&feature_sample_command("my-app"))
+            .dynamic(&completers)
+            .to_string()
+    );
+
+    let examples = nu::Examples::new()
+        .add(
+            &[],
+            nu::Example::new("Show the version", "my-app --version").result("my-app"),
+        )
+        .add(
+            &["check"],
+            nu::Example::new("Check \"input\"", "my-app check input"),
+        );
+    insta::assert_snapshot!(
+        "nu/examples-registered",
+        nu::Completions::new(&examples_command("my-app"))
+            .examples(&examples)
+            .to_string()
+    );
+    insta::assert_snapshot!(
+        "nu/0.102/examples-registered",
+        nu::Completions::new(&examples_command("my-app"))
+            .examples(&examples)
+            .version(nu::NuVersion::new(0, 102))
+            .to_string()
+    );
+
+    // Parse the completions generated for the installed nushell, which match
+    // the snapshots recorded for the same features.
+    let version = match nu_version() {
+        Some(version) => version,
+        None => return,
+    };
+    for fixture in FIXTURES {
+        let mut app = fixture("my-app");
+        app.build();
+        run_nu(&nu::Completions::new(&app).version(version).to_string(), "");
+    }
+    if version != nu::NuVersion::LATEST {
+        return;
+    }
+
     macro_rules! test {
         ($f:ident) => {
             &{
                 let mut app = $f("my-app");
                 app.build();
-                get_nu_help(&app)?
+                get_nu_help(&app).unwrap()
             }
         };
     }
 
-    let _ = (|| -> Result<(), std::io::Error> {
-        all_tests!("nu/help", test);
-        Ok(())
-    })();
+    all_tests!("nu/help", test);
+}
+
+#[test]
+#[cfg(feature = "nu")]
+fn nu_completers() {
+    use clap_completions::nu;
+
+    let version = match nu_version() {
+        Some(version) => version,
+        None => return,
+    };
+
+    // The values offered by `call`, an expression calling a helper.
+    let values = |app: clap::Command<'static>, call: &str| {
+        let mut app = app;
+        app.build();
+        // Helpers are private to the module, so call them from within it.
+        let module = format!(
+            "{}\nexport def probe [] {{ {call} \
+            | if ($in | describe) starts-with 'record' {{ $in.completions }} else {{ $in }} \
+            | each {{|c| if ($c | describe) == 'string' {{ $c }} else {{ $c.value }} }} \
+            | to json -r }}",
+            nu::Completions::new(&app).version(version),
+        );
+        run_nu(&module, "probe").trim().to_string()
+    };
+    // The values a helper offers for the command line `context`, as nushell
+    // replaces only the value of a `--flag=value` word with them.
+    let complete = |app, helper: &str, context: &str| values(app, &format!("{helper} {context:?}"));
+
+    let color = "nu-complete my-app some_cmd --color";
+    assert_eq!(
+        complete(
+            special_commands_command("my-app"),
+            color,
+            "my-app some_cmd --color=au"
+        ),
+        r#"["auto","always","never"]"#
+    );
+    assert_eq!(
+        complete(
+            special_commands_command("my-app"),
+            color,
+            "my-app some_cmd --color "
+        ),
+        "[]"
+    );
+
+    let help = "nu-complete my-app help --subcommand";
+    assert_eq!(
+        complete(aliases_command("my-app"), help, "my-app help t "),
+        r#"["unit","help"]"#
+    );
+
+    let features = "nu-complete my-app --features";
+    assert_eq!(
+        complete(
+            value_delimiter_command("my-app"),
+            features,
+            "my-app --features=std,se"
+        ),
+        r#"["std,serde","std,derive"]"#
+    );
+    assert_eq!(
+        complete(
+            value_delimiter_command("my-app"),
+            features,
+            "my-app --features "
+        ),
+        r#"["std","serde","derive"]"#
+    );
+
+    // Only executable files on `PATH` are offered.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir =
+            std::env::temp_dir().join(format!("clap-completions-path-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, mode) in [("tool", 0o755), ("notes", 0o644)] {
+            let path = dir.join(file);
+            std::fs::write(&path, "").unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        }
+        let app = clap::Command::new("my-app").arg(
+            clap::Arg::new("exe")
+                .long("exe")
+                .value_hint(clap::ValueHint::ExecutablePath),
+        );
+        let call = format!(
+            "with-env {{ PATH: [{:?}] }} {{ nu-complete my-app --exe }}",
+            dir
+        );
+        assert_eq!(values(app, &call), r#"["tool"]"#);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
 
 pub fn basic_command(name: &'static str) -> clap::Command<'static> {
@@ -118,6 +364,23 @@ pub fn special_commands_command(name: &'static str) -> clap::Command<'static> {
                         .require_equals(true)
                         .help("the other case to test"),
                 )
+                .arg(
+                    clap::Arg::new("color")
+                        .long("color")
+                        .short('c')
+                        .takes_value(true)
+                        .require_equals(true)
+                        .value_parser(["auto", "always", "never"])
+                        .help("when to color output"),
+                )
+                .arg(
+                    clap::Arg::new("jobs")
+                        .long("jobs")
+                        .value_name("N")
+                        .takes_value(true)
+                        .require_equals(true)
+                        .help("number of parallel jobs"),
+                )
                 .arg(
                     clap::Arg::new("path")
                         .takes_value(true)
@@ -161,6 +424,17 @@ pub fn quoting_command(name: &'static str) -> clap::Command<'static> {
                 .long("expansions")
                 .help("Execute the shell command with $SHELL"),
         )
+        .arg(
+            clap::Arg::new("values")
+                .long("values")
+                .takes_value(true)
+                .value_parser([
+                    clap::PossibleValue::new("back\\slash").help("Avoid '\\n'"),
+                    clap::PossibleValue::new("\"quoted\"").help("Can be \"always\""),
+                    clap::PossibleValue::new("new\nline"),
+                ]),
+        )
+        .arg(clap::Arg::new("it's positional"))
         .subcommands([
             clap::Command::new("cmd-single-quotes").about("Can be 'always', 'auto', or 'never'"),
             clap::Command::new("cmd-double-quotes")
@@ -194,6 +468,23 @@ pub fn aliases_command(name: &'static str) -> clap::Command<'static> {
                 .takes_value(true),
         )
         .arg(clap::Arg::new("positional"))
+        .subcommand(
+            clap::Command::new("test")
+                .about("tests things")
+                .visible_alias("t")
+                .alias("hidden-alias")
+                .short_flag('T')
+                .long_flag("test")
+                .visible_long_flag_alias("check")
+                .arg(
+                    clap::Arg::new("case")
+                        .long("case")
+                        .takes_value(true)
+                        .value_parser(["one", "two"])
+                        .help("the case to test"),
+                )
+                .subcommand(clap::Command::new("unit").about("runs unit tests")),
+        )
 }
 
 pub fn sub_subcommands_command(name: &'static str) -> clap::Command<'static> {
@@ -219,7 +510,12 @@ pub fn value_hint_command(name: &'static str) -> clap::Command<'static> {
             clap::Arg::new("choice")
                 .long("choice")
                 .takes_value(true)
-                .value_parser(["bash", "fish", "zsh"]),
+                .value_parser([
+                    clap::PossibleValue::new("bash").help("GNU Bourne-Again SHell"),
+                    clap::PossibleValue::new("fish"),
+                    clap::PossibleValue::new("zsh").help("Z SHell"),
+                    clap::PossibleValue::new("sh").hide(true),
+                ]),
         )
         .arg(
             clap::Arg::new("unknown")
@@ -231,6 +527,12 @@ pub fn value_hint_command(name: &'static str) -> clap::Command<'static> {
                 .long("other")
                 .value_hint(clap::ValueHint::Other),
         )
+        .arg(
+            clap::Arg::new("other_choice")
+                .long("other-choice")
+                .value_hint(clap::ValueHint::Other)
+                .value_parser(["alpha", "beta"]),
+        )
         .arg(
             clap::Arg::new("path")
                 .long("path")
@@ -295,3 +597,374 @@ pub fn value_hint_command(name: &'static str) -> clap::Command<'static> {
                 .value_hint(clap::ValueHint::EmailAddress),
         )
 }
+
+pub fn value_parser_command(name: &'static str) -> clap::Command<'static> {
+    clap::Command::new(name)
+        .arg(
+            clap::Arg::new("count")
+                .takes_value(true)
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            clap::Arg::new("port")
+                .long("port")
+                .takes_value(true)
+                .value_parser(clap::value_parser!(u16)),
+        )
+        .arg(
+            clap::Arg::new("level")
+                .long("level")
+                .takes_value(true)
+                .value_parser(clap::value_parser!(i64).range(0..10)),
+        )
+        .arg(
+            clap::Arg::new("ratio")
+                .long("ratio")
+                .takes_value(true)
+                .value_parser(clap::value_parser!(f64)),
+        )
+        .arg(
+            clap::Arg::new("color")
+                .long("color")
+                .takes_value(true)
+                .value_parser(clap::value_parser!(bool)),
+        )
+        .arg(
+            clap::Arg::new("fancy")
+                .long("fancy")
+                .takes_value(true)
+                .value_parser(clap::builder::BoolishValueParser::new()),
+        )
+        .arg(
+            clap::Arg::new("quiet")
+                .long("quiet")
+                .takes_value(true)
+                .value_parser(clap::builder::FalseyValueParser::new()),
+        )
+        .arg(
+            clap::Arg::new("out")
+                .long("out")
+                .takes_value(true)
+                .value_parser(clap::value_parser!(std::path::PathBuf)),
+        )
+        .arg(
+            clap::Arg::new("name")
+                .long("name")
+                .takes_value(true)
+                .value_parser(clap::value_parser!(std::ffi::OsString)),
+        )
+        .arg(
+            clap::Arg::new("mode")
+                .long("mode")
+                .takes_value(true)
+                .ignore_case(true)
+                .value_parser(["Fast", "Slow", "Auto"]),
+        )
+}
+
+pub fn occurrences_command(name: &'static str) -> clap::Command<'static> {
+    clap::Command::new(name)
+        .arg(
+            clap::Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .action(clap::ArgAction::Count)
+                .help("use verbose output (-vv very verbose)"),
+        )
+        .arg(
+            clap::Arg::new("features")
+                .short('F')
+                .long("features")
+                .takes_value(true)
+                .multiple_values(true)
+                .help("features to activate"),
+        )
+        .arg(
+            clap::Arg::new("include")
+                .short('I')
+                .takes_value(true)
+                .action(clap::ArgAction::Append),
+        )
+        .arg(
+            clap::Arg::new("define")
+                .short('D')
+                .long("define")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .help("define a variable"),
+        )
+        .arg(
+            clap::Arg::new("point")
+                .long("point")
+                .takes_value(true)
+                .number_of_values(2)
+                .help("a coordinate pair"),
+        )
+        .arg(
+            clap::Arg::new("files")
+                .takes_value(true)
+                .multiple_occurrences(true),
+        )
+        .subcommand(
+            clap::Command::new("plot")
+                .about("No rest positional")
+                .arg(
+                    clap::Arg::new("point")
+                        .long("point")
+                        .takes_value(true)
+                        .number_of_values(2)
+                        .help("a coordinate pair"),
+                )
+                .arg(
+                    clap::Arg::new("label")
+                        .long("label")
+                        .takes_value(true)
+                        .multiple_values(true)
+                        .help("labels to show"),
+                )
+                .arg(
+                    clap::Arg::new("series")
+                        .short('s')
+                        .takes_value(true)
+                        .multiple_values(true)
+                        .multiple_occurrences(true)
+                        .help("series to plot"),
+                )
+                .arg(
+                    clap::Arg::new("range")
+                        .long("range")
+                        .takes_value(true)
+                        .number_of_values(2)
+                        .value_delimiter(':')
+                        .help("the range to plot"),
+                ),
+        )
+}
+
+pub fn positionals_command(name: &'static str) -> clap::Command<'static> {
+    clap::Command::new(name)
+        .subcommand(
+            clap::Command::new("copy")
+                .about("Indices differ from declaration order")
+                .arg(
+                    clap::Arg::new("dst")
+                        .index(2)
+                        .required(true)
+                        .help("destination"),
+                )
+                .arg(
+                    clap::Arg::new("src")
+                        .index(1)
+                        .required(true)
+                        .value_name("SOURCE")
+                        .help("source"),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("optional")
+                .about("Required after optional")
+                .allow_missing_positional(true)
+                .arg(clap::Arg::new("first").help("may be left out"))
+                .arg(clap::Arg::new("second").required(true)),
+        )
+        .subcommand(
+            clap::Command::new("build")
+                .about("Trailing args after --")
+                .arg(
+                    clap::Arg::new("target")
+                        .takes_value(true)
+                        .multiple_values(true)
+                        .help("targets to build"),
+                )
+                .arg(
+                    clap::Arg::new("args")
+                        .takes_value(true)
+                        .multiple_values(true)
+                        .last(true)
+                        .help("arguments passed along"),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("connect")
+                .about("Value names")
+                .arg(
+                    clap::Arg::new("via")
+                        .required(true)
+                        .value_name("proxy")
+                        .help("named like a flag"),
+                )
+                .arg(
+                    clap::Arg::new("addr")
+                        .value_names(&["HOST", "PORT"])
+                        .help("address to connect to"),
+                )
+                .arg(
+                    clap::Arg::new("proxy")
+                        .long("proxy")
+                        .value_names(&["HOST", "PORT"])
+                        .help("proxy to connect through"),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("exec")
+                .about("Trailing var arg")
+                .trailing_var_arg(true)
+                .arg(clap::Arg::new("program").required(true))
+                .arg(
+                    clap::Arg::new("args")
+                        .takes_value(true)
+                        .multiple_values(true)
+                        .allow_hyphen_values(true)
+                        .help("arguments to the program"),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("remove")
+                .about("Required multiple values")
+                .arg(
+                    clap::Arg::new("files")
+                        .required(true)
+                        .takes_value(true)
+                        .multiple_values(true)
+                        .help("files to remove"),
+                ),
+        )
+}
+
+pub fn long_help_command(name: &'static str) -> clap::Command<'static> {
+    clap::Command::new(name)
+        .about("Summarize the input")
+        .long_about(
+            "Summarize the input\n\
+             \n\
+             Reads every file given and prints a summary of its contents.\n\
+             Files are read in order.",
+        )
+        .before_help("Note: this is an example.")
+        .after_help("See also: my-app help")
+        .after_long_help("See the manual for more details.")
+        .arg(
+            clap::Arg::new("format")
+                .long("format")
+                .takes_value(true)
+                .help("output format")
+                .long_help(
+                    "Output format\n\
+                     \n\
+                     The format of the summary, either text or json.\n\
+                     Defaults to text.",
+                ),
+        )
+        .subcommand(
+            clap::Command::new("check")
+                .about("Check the input\nwithout summarizing it")
+                .after_help("EXIT STATUS:\n    0 if the input is valid\n    1 otherwise"),
+        )
+}
+
+pub fn examples_command(name: &'static str) -> clap::Command<'static> {
+    clap::Command::new(name)
+        .about("Process input")
+        .after_help(
+            "EXAMPLES:\n\
+             \x20   # Process the default input\n\
+             \x20   $ my-app\n\
+             \n\
+             \x20   Process input quietly:\n\
+             \x20       my-app --quiet\n\
+             \n\
+             See the manual for more details.",
+        )
+        .arg(clap::Arg::new("quiet").long("quiet").help("print nothing"))
+        .subcommand(
+            clap::Command::new("check")
+                .about("Check input")
+                .after_help("Examples:\n    my-app check input.txt\n    my-app check -"),
+        )
+}
+
+pub fn shared_values_command(name: &'static str) -> clap::Command<'static> {
+    let color = || {
+        clap::Arg::new("color")
+            .long("color")
+            .takes_value(true)
+            .value_parser(["auto", "always", "never"])
+    };
+    let format = |values: &'static [&'static str]| {
+        clap::Arg::new("format")
+            .long("format")
+            .takes_value(true)
+            .value_parser(clap::builder::PossibleValuesParser::new(values))
+    };
+    clap::Command::new(name)
+        .arg(color())
+        .subcommand(
+            clap::Command::new("build")
+                .arg(color())
+                .arg(format(&["human", "json"])),
+        )
+        .subcommand(
+            clap::Command::new("test")
+                .arg(color().ignore_case(true))
+                .arg(format(&["human", "json"]))
+                .arg(
+                    clap::Arg::new("message-format")
+                        .long("message-format")
+                        .takes_value(true)
+                        .value_parser(["human", "json"]),
+                ),
+        )
+        .subcommand(clap::Command::new("doc").arg(format(&["json", "human"])))
+}
+
+pub fn external_subcommands_command(name: &'static str) -> clap::Command<'static> {
+    clap::Command::new(name)
+        .allow_external_subcommands(true)
+        .subcommand(
+            clap::Command::new("build")
+                .visible_alias("b")
+                .about("Build the project"),
+        )
+        .subcommand(
+            clap::Command::new("remote")
+                .about("Manage remotes")
+                .allow_external_subcommands(true)
+                .subcommand(clap::Command::new("add").about("Add a remote")),
+        )
+}
+
+pub fn value_delimiter_command(name: &'static str) -> clap::Command<'static> {
+    clap::Command::new(name)
+        .arg(
+            clap::Arg::new("features")
+                .long("features")
+                .help("Features to activate")
+                .multiple_values(true)
+                .value_delimiter(',')
+                .value_parser(["std", "serde", "derive"]),
+        )
+        .arg(
+            clap::Arg::new("target")
+                .long("target")
+                .help("Targets to build for")
+                .multiple_values(true)
+                .value_delimiter(',')
+                .value_parser([
+                    clap::PossibleValue::new("x86_64").help("64-bit x86"),
+                    clap::PossibleValue::new("aarch64").help("64-bit ARM"),
+                ]),
+        )
+        .arg(
+            clap::Arg::new("cfg")
+                .long("cfg")
+                .help("Configuration flags")
+                .use_value_delimiter(true),
+        )
+        .arg(
+            clap::Arg::new("paths")
+                .help("Search paths")
+                .multiple_values(true)
+                .value_delimiter(':')
+                .value_parser(["bin", "lib", "share"]),
+        )
+}
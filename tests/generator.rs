@@ -0,0 +1,80 @@
+use clap_completions::{generate_to, Generator};
+
+fn app() -> clap::Command<'static> {
+    clap::Command::new("my-app").subcommand(clap::Command::new("test"))
+}
+
+#[test]
+#[cfg(feature = "nu")]
+fn nu() {
+    use clap_completions::nu;
+
+    let app = app();
+    let shell = nu::Nu::new();
+    assert_eq!(shell.name(), "nu");
+    assert_eq!(shell.file_name("my-app"), "my-app.nu");
+
+    // Like `clap_complete::Shell`, the command is built before generating.
+    let mut built = app.clone();
+    built.build();
+    let mut buf = Vec::new();
+    shell.generate(&app, &mut buf).unwrap();
+    let generated = String::from_utf8(buf).unwrap();
+    assert_eq!(generated, nu::Completions::new(&built).to_string());
+    assert!(generated.contains("export extern 'my-app help'"));
+}
+
+#[test]
+#[cfg(feature = "clap_complete")]
+fn clap_complete() {
+    use clap_complete::Shell;
+
+    assert_eq!(Shell::Bash.name(), "bash");
+    assert_eq!(Generator::file_name(&Shell::Bash, "my-app"), "my-app.bash");
+    assert_eq!(Generator::file_name(&Shell::Zsh, "my-app"), "_my-app");
+
+    let mut buf = Vec::new();
+    Generator::generate(&Shell::Fish, &app(), &mut buf).unwrap();
+    assert!(String::from_utf8(buf).unwrap().contains("my-app"));
+}
+
+#[test]
+fn uniform_generate_to() {
+    let shells: Vec<Box<dyn Generator>> = vec![
+        #[cfg(feature = "nu")]
+        Box::new(clap_completions::nu::Nu::new()),
+        #[cfg(feature = "clap_complete")]
+        Box::new(clap_complete::Shell::Bash),
+    ];
+
+    let out_dir = std::env::temp_dir().join(format!("clap-completions-{}", std::process::id()));
+    std::fs::create_dir_all(&out_dir).unwrap();
+    for shell in &shells {
+        let path = generate_to(shell.as_ref(), &app(), &out_dir).unwrap();
+        assert_eq!(path, out_dir.join(shell.file_name("my-app")));
+        assert!(!std::fs::read_to_string(path).unwrap().is_empty());
+    }
+    std::fs::remove_dir_all(out_dir).unwrap();
+}
+
+#[test]
+fn display_name() {
+    let shells: Vec<Box<dyn Generator>> = vec![
+        #[cfg(feature = "nu")]
+        Box::new(clap_completions::nu::Nu::new()),
+        #[cfg(feature = "clap_complete")]
+        Box::new(clap_complete::Shell::Fish),
+    ];
+
+    // Every shell invokes a command without a bin name by its display name.
+    let app = app().display_name("my-tool");
+    let out_dir =
+        std::env::temp_dir().join(format!("clap-completions-display-{}", std::process::id()));
+    std::fs::create_dir_all(&out_dir).unwrap();
+    for shell in &shells {
+        let path = generate_to(shell.as_ref(), &app, &out_dir).unwrap();
+        assert_eq!(path, out_dir.join(shell.file_name("my-tool")));
+        assert!(std::fs::read_to_string(path).unwrap().contains("my-tool"));
+    }
+    std::fs::remove_dir_all(out_dir).unwrap();
+}
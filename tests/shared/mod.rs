@@ -1,5 +1,4 @@
 use clap::Command;
-use std::{io, process};
 
 pub fn get_clap_help(app: &mut Command) -> String {
     use std::io::Write;
@@ -15,8 +14,57 @@ pub fn get_clap_help(app: &mut Command) -> String {
     String::from_utf8(buf).unwrap()
 }
 
+/// Set to check generated completions with nushell, which must then be
+/// installed; otherwise the checks are skipped. CI sets it for each nushell
+/// release the tests target.
 #[cfg(feature = "nu")]
-pub fn get_nu_help(app: &Command) -> io::Result<String> {
+pub const NU_TESTS: &str = "CLAP_COMPLETIONS_NU_TESTS";
+
+/// The installed nushell release, if checks are enabled through [`NU_TESTS`].
+#[cfg(feature = "nu")]
+pub fn nu_version() -> Option<clap_completions::nu::NuVersion> {
+    std::env::var_os(NU_TESTS)?;
+
+    let out = std::process::Command::new("nu")
+        .arg("--version")
+        .output()
+        .unwrap_or_else(|err| panic!("{NU_TESTS} is set, but nu could not be run: {err}"));
+    let version = String::from_utf8(out.stdout).unwrap();
+    let mut parts = version.trim().split('.').map(|part| part.parse().unwrap());
+    let (major, minor) = (parts.next().unwrap(), parts.next().unwrap());
+    Some(clap_completions::nu::NuVersion::new(major, minor))
+}
+
+/// Run `program` with nushell, after bringing the completions `module` into
+/// scope, returning its output.
+#[cfg(feature = "nu")]
+pub fn run_nu(module: &str, program: &str) -> String {
+    let program = format!("module m {{\n{module}\n}}\nuse m *\n{program}");
+    let out = std::process::Command::new("nu")
+        .arg("-c")
+        .arg(&program)
+        .output()
+        .unwrap_or_else(|err| panic!("{NU_TESTS} is set, but nu could not be run: {err}"));
+
+    if out.status.success() {
+        String::from_utf8(out.stdout).unwrap()
+    } else {
+        panic!(
+            "nu failed: {}",
+            program + &String::from_utf8(out.stderr).unwrap()
+        );
+    }
+}
+
+/// The output of nushell's `help` for every command of `app`, if nushell
+/// checks are enabled through [`NU_TESTS`].
+///
+/// Snapshots of the output are recorded with [`NuVersion::LATEST`], so this
+/// is skipped for other releases.
+///
+/// [`NuVersion::LATEST`]: clap_completions::nu::NuVersion::LATEST
+#[cfg(feature = "nu")]
+pub fn get_nu_help(app: &Command) -> Option<String> {
     use clap_completions::nu;
 
     fn build_nu_help<'a>(app: &'a Command<'_>, parent: &mut Vec<&'a str>) -> String {
@@ -37,21 +85,10 @@ pub fn get_nu_help(app: &Command) -> io::Result<String> {
         buf
     }
 
-    let mut program = nu::Completions::new(app).to_string();
-    program = format!("module m {{\n{program}\n}}\nuse m *\n");
-    program += &build_nu_help(app, &mut vec![]);
-
-    let out = process::Command::new("nu")
-        .arg("-c")
-        .arg(&program)
-        .output()?;
-
-    if out.status.success() {
-        Ok(String::from_utf8(out.stdout).unwrap())
-    } else {
-        panic!(
-            "nu failed: {}",
-            program + &String::from_utf8(out.stderr).unwrap()
-        );
+    if nu_version()? != nu::NuVersion::LATEST {
+        return None;
     }
+
+    let module = nu::Completions::new(app).to_string();
+    Some(run_nu(&module, &build_nu_help(app, &mut vec![])))
 }